@@ -0,0 +1,117 @@
+use crate::define::*;
+use crate::import::*;
+
+// What the announcement shows, built under the lock so the message is sent without it
+pub struct Announcement {
+    pub track: TrackSympho,
+    pub queue_len: usize,
+    pub up_next: Option<String>,
+}
+
+// The song that just started, None if it was announced already.
+// `Play` is raised on resume too, only the first one of each song is announced
+pub fn announcement_of(sympho_data: &mut SymphoData, track: &TrackHandle) -> Option<Announcement> {
+    if !sympho_data.settings.announce || sympho_data.announced {
+        return None;
+    }
+
+    let current = match &sympho_data.current {
+        Some((current, current_track)) if current.uuid() == track.uuid() => current_track.clone(),
+        _ => return None,
+    };
+    sympho_data.announced = true;
+
+    Some(Announcement {
+        track: current,
+        queue_len: sympho_data.queue.len(),
+        up_next: sympho_data.queue.first().map(|track| track.title.clone()),
+    })
+}
+
+fn announcement_embed<'a>(
+    e: &'a mut CreateEmbed,
+    announcement: &Announcement,
+) -> &'a mut CreateEmbed {
+    let track = &announcement.track;
+
+    e.title(track.title.clone());
+    e.url(&track.url);
+    e.field(
+        "Duration",
+        if track.is_live {
+            "Live".to_string()
+        } else {
+            dur_to_hhmmss(track.duration)
+        },
+        true,
+    );
+    if let Some(requester) = track.requester {
+        e.field("Requested by", requester.mention(), true);
+    }
+    e.field(
+        "Queue",
+        format!("{} songs left", announcement.queue_len),
+        true,
+    );
+    if let Some(up_next) = &announcement.up_next {
+        e.field("Up next", up_next, false);
+    }
+    if let Some(thumb_url) = &track.thumb {
+        e.thumbnail(thumb_url);
+    }
+
+    e
+}
+
+// Post the song that just started to `chan_id`.
+// the previous announcement is deleted if the guild wants it
+pub async fn announce_track(
+    http: &Arc<Http>,
+    sympho_global_mutex: &SymphoMap,
+    key: u64,
+    chan_id: ChannelId,
+    track: &TrackHandle,
+) {
+    let announcement = {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        if let Some(sympho_data) = sympho_global.get_mut(&key) {
+            announcement_of(sympho_data, track)
+        } else {
+            None
+        }
+    };
+    let announcement = if let Some(announcement) = announcement {
+        announcement
+    } else {
+        return;
+    };
+
+    let message = match chan_id
+        .send_message(http, |m| {
+            m.content("_now playing ♪:_");
+            m.embed(|e| announcement_embed(e, &announcement));
+            m
+        })
+        .await
+    {
+        Ok(message) => message,
+        Err(why) => {
+            println!("Error sending message: {:?}", why);
+            return;
+        }
+    };
+
+    let old_announcement = {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        if let Some(sympho_data) = sympho_global.get_mut(&key) {
+            let old_announcement = sympho_data.announcement.replace((chan_id, message.id));
+            old_announcement.filter(|_| sympho_data.settings.announce_cleanup)
+        } else {
+            None
+        }
+    };
+
+    if let Some((old_chan_id, old_message_id)) = old_announcement {
+        let _ = old_chan_id.delete_message(http, old_message_id).await;
+    }
+}
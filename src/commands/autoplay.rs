@@ -0,0 +1,55 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const AUTOPLAY_USAGE: &str = "usage: <PREFIX>autoplay on or <PREFIX>autoplay off";
+
+#[command]
+#[aliases("radio")]
+#[only_in(guilds)]
+#[description("Enable/Disable autoplay. when the queue runs dry, a song related to the last one is played.\nusage: <PREFIX>autoplay on")]
+async fn autoplay(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let autoplay = match args.single::<String>().as_deref() {
+        Ok("on") => true,
+        Ok("off") => false,
+        _ => return Err(SymphoError::Usage(AUTOPLAY_USAGE.to_string()).into()),
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            sympho_data.autoplay = autoplay;
+
+            check_msg(
+                msg.reply(
+                    ctx,
+                    if autoplay {
+                        "Enabled autoplay, related songs are played when the queue runs dry."
+                    } else {
+                        "Disabled autoplay."
+                    },
+                )
+                .await,
+            );
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+#[command]
+#[only_in(guilds)]
+#[description("Make the queue empty, the music currently playing is kept.")]
+async fn clear(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            sympho_data.clear_queue();
+        }
+
+        check_msg(msg.reply(&ctx.http, "Queue was cleared.").await);
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+use crate::permission::*;
+use crate::settings::*;
+
+const CONFIG_USAGE: &str = "example usage: <PREFIX>config get -> show all settings.
+example usage: <PREFIX>config set prefix ? -> use `?` as the prefix.
+example usage: <PREFIX>config set volume 30 -> start with 30% volume.
+example usage: <PREFIX>config set channel #music -> accept the commands only in #music(`here` for this channel, `off` for all channels).
+example usage: <PREFIX>config set dj_roles DJUser,@Music -> the roles that can use the dj commands.
+example usage: <PREFIX>config set voteskip_ratio 0.5 -> half of the listeners are needed for voteskip.
+example usage: <PREFIX>config set announce on -> post the songs when they start.
+example usage: <PREFIX>config set announce_cleanup on -> delete the previous post when the next song starts.
+example usage: <PREFIX>config set alone_timeout 1:00 -> leave when no one is listening for 1 minute, the song is paused meanwhile.
+example usage: <PREFIX>config set idle_timeout 10:00 -> leave when nothing was played for 10 minutes.
+example usage: <PREFIX>config reset prefix -> back to the default, or all settings without the key.";
+
+pub const CONFIG_KEYS: &[&str] = &[
+    "prefix",
+    "volume",
+    "channel",
+    "dj_roles",
+    "voteskip_ratio",
+    "announce",
+    "announce_cleanup",
+    "alone_timeout",
+    "idle_timeout",
+];
+
+fn on_off(on: bool) -> String {
+    if on { "on" } else { "off" }.to_string()
+}
+
+fn describe_timeout(timeout: Option<Duration>) -> String {
+    timeout
+        .map(dur_to_hhmmss)
+        .unwrap_or_else(|| "off".to_string())
+}
+
+// `off`, or a timestamp like `5:00`
+fn parse_timeout(value: &str) -> Option<Option<Duration>> {
+    if value == "off" {
+        Some(None)
+    } else {
+        parse_timestamp(value).map(Some)
+    }
+}
+
+fn parse_on_off(value: &str) -> Option<bool> {
+    match value {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn describe_setting(settings: &GuildSettings, key: &str) -> String {
+    match key {
+        "prefix" => settings
+            .prefix
+            .clone()
+            .unwrap_or_else(|| format!("{} (default)", default_prefix())),
+        "volume" => format!("{}", settings.volume * 100.0),
+        "channel" => settings
+            .bound_channel
+            .map(|channel_id| ChannelId(channel_id).mention().to_string())
+            .unwrap_or_else(|| "off".to_string()),
+        "dj_roles" => settings.permissions.dj_roles.join(","),
+        "voteskip_ratio" => format!("{}", settings.voteskip_ratio),
+        "announce" => on_off(settings.announce),
+        "announce_cleanup" => on_off(settings.announce_cleanup),
+        "alone_timeout" => describe_timeout(settings.alone_timeout),
+        "idle_timeout" => describe_timeout(settings.idle_timeout),
+        _ => String::new(),
+    }
+}
+
+fn set_setting(
+    settings: &mut GuildSettings,
+    key: &str,
+    value: &str,
+    here: ChannelId,
+) -> Option<()> {
+    match key {
+        "prefix" if !value.is_empty() && value.chars().count() <= 5 => {
+            settings.prefix = Some(value.to_string());
+        }
+        "volume" => {
+            let volume = value.parse::<f32>().ok()?;
+            if !(0.1..=100.0).contains(&volume) {
+                return None;
+            }
+            settings.volume = volume / 100.0;
+        }
+        "channel" => {
+            settings.bound_channel = match value {
+                "here" => Some(here.0),
+                "off" => None,
+                channel => Some(
+                    channel
+                        .strip_prefix("<#")
+                        .and_then(|channel| channel.strip_suffix('>'))
+                        .unwrap_or(channel)
+                        .parse::<u64>()
+                        .ok()?,
+                ),
+            };
+        }
+        "dj_roles" => {
+            settings.permissions.dj_roles = value
+                .split(',')
+                .map(|role| parse_role(role.trim()))
+                .filter(|role| !role.is_empty())
+                .collect();
+        }
+        "voteskip_ratio" => {
+            let ratio = value.parse::<f32>().ok()?;
+            if ratio <= 0.0 || ratio > 1.0 {
+                return None;
+            }
+            settings.voteskip_ratio = ratio;
+        }
+        "announce" => settings.announce = parse_on_off(value)?,
+        "announce_cleanup" => settings.announce_cleanup = parse_on_off(value)?,
+        "alone_timeout" => settings.alone_timeout = parse_timeout(value)?,
+        "idle_timeout" => settings.idle_timeout = parse_timeout(value)?,
+        _ => return None,
+    }
+
+    Some(())
+}
+
+fn reset_setting(settings: &mut GuildSettings, key: &str) {
+    let default = GuildSettings::default();
+    match key {
+        "prefix" => settings.prefix = default.prefix,
+        "volume" => settings.volume = default.volume,
+        "channel" => settings.bound_channel = default.bound_channel,
+        "dj_roles" => settings.permissions.dj_roles = default.permissions.dj_roles,
+        "voteskip_ratio" => settings.voteskip_ratio = default.voteskip_ratio,
+        "announce" => settings.announce = default.announce,
+        "announce_cleanup" => settings.announce_cleanup = default.announce_cleanup,
+        "alone_timeout" => settings.alone_timeout = default.alone_timeout,
+        "idle_timeout" => settings.idle_timeout = default.idle_timeout,
+        _ => {}
+    }
+}
+
+#[command]
+#[only_in(guilds)]
+#[description("Show or change the settings of the server.\nusage: <PREFIX>config get Option<key>, <PREFIX>config set <key> <value>, <PREFIX>config reset Option<key>\nkeys: prefix, volume, channel, dj_roles, voteskip_ratio, announce, announce_cleanup, alone_timeout, idle_timeout")]
+async fn config(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let action = args.single::<String>().unwrap_or_default();
+    let key = args.single::<String>().ok();
+    let value = args.rest().trim().to_string();
+
+    if let Some(key) = &key {
+        if !CONFIG_KEYS.contains(&key.as_str()) {
+            return Err(SymphoError::Usage(format!(
+                "`{}` is not a setting, keys: {}\n{}",
+                key,
+                CONFIG_KEYS.join(", "),
+                CONFIG_USAGE
+            ))
+            .into());
+        }
+    }
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+
+    let data = ctx.data.read().await;
+    if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+        let settings = &mut sympho_data.settings;
+
+        match (action.as_str(), key.as_deref()) {
+            ("get", _) => {}
+            ("set", Some(key)) => {
+                if set_setting(settings, key, &value, msg.channel_id).is_none() {
+                    return Err(SymphoError::Usage(CONFIG_USAGE.to_string()).into());
+                }
+            }
+            ("reset", Some(key)) => reset_setting(settings, key),
+            ("reset", None) => *settings = GuildSettings::default(),
+            _ => return Err(SymphoError::Usage(CONFIG_USAGE.to_string()).into()),
+        }
+
+        let keys = key
+            .as_deref()
+            .map(|key| vec![key])
+            .unwrap_or_else(|| CONFIG_KEYS.to_vec());
+        let reply = keys
+            .iter()
+            .map(|key| format!("{}: {}", key, describe_setting(settings, key)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        drop(sympho_global);
+
+        check_msg(msg.reply(&ctx.http, reply).await);
+
+        if action != "get" {
+            save_settings(sympho_global_mutex).await;
+        }
+    }
+
+    Ok(())
+}
@@ -1,124 +1,49 @@
 use crate::define::*;
+use crate::error::*;
 use crate::import::*;
+use crate::panel::*;
 
 #[command]
 #[aliases("np", "nowplaying", "なうぷれ")]
 #[only_in(guilds)]
-#[description("Shows the info of the music currently playing.")]
+#[description("Shows the info of the music currently playing, with the buttons to control it.\nthe message is kept up to date, and the old one is deleted when it's posted again.")]
 async fn current(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
     let guild = if let Some(g) = msg.guild(&ctx.cache) {
         g
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot could'nt get the guild information, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoGuild.into());
     };
     let guild_id = guild.id;
     let manager = if let Some(m) = songbird::get(ctx).await {
         m
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoSongbird.into());
     };
 
     if let Some(_handler_lock) = manager.get(guild_id) {
-        let data = ctx.data.read().await;
-        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
-            let mut sympho_global = sympho_global_mutex.write().await;
-            let sympho_data = sympho_global.entry(guild_id.0).or_insert(SymphoData {
-                volume: 1.0,
-                ..Default::default()
-            });
-
-            if let Some((track_handle, track_sympho)) = &sympho_data.current {
-                say_track_with_embed(msg, ctx, track_handle, track_sympho).await;
+        let sympho_global_mutex = {
+            let data = ctx.data.read().await;
+            if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+                sympho_global_mutex.clone()
             } else {
-                check_msg(msg.reply(&ctx.http, "No songs.").await);
-            };
+                return Ok(());
+            }
+        };
+
+        let is_playing = sympho_global_mutex
+            .read()
+            .await
+            .get(&guild_id.0)
+            .map_or(false, |sympho_data| sympho_data.current.is_some());
+
+        if is_playing {
+            post_panel(&ctx.http, &sympho_global_mutex, guild_id.0, msg.channel_id).await;
+        } else {
+            check_msg(msg.reply(&ctx.http, "No songs.").await);
         }
     } else {
-        check_msg(
-            msg.reply(ctx, "The bot is not in a voice channel. >_<!")
-                .await,
-        );
+        return Err(SymphoError::NotInVoice.into());
     }
 
     Ok(())
 }
-
-async fn say_track_with_embed(
-    msg: &Message,
-    ctx: &Context,
-    track_handle: &TrackHandle,
-    track_sympho: &TrackSympho,
-) {
-    let track_current_position = if let Ok(info) = track_handle.get_info().await {
-        dur_to_hhmmss(info.position)
-    } else {
-        "Unknown".to_string()
-    };
-
-    check_msg(
-        msg.channel_id
-            .send_message(&ctx.http, |m| {
-                m.content("_nowplaying ♡:_");
-                m.embed(|e| {
-                    e.author(|a| {
-                        if let Ok(icon) = SYMPHO_ICON
-                            .get_or_init(|| {
-                                Mutex::new(
-                                    "https://cdn.discordapp.com/embed/avatars/0.png".to_string(),
-                                )
-                            })
-                            .lock()
-                        {
-                            a.icon_url(icon);
-                        }
-
-                        if let Ok(name) = SYMPHO_NAME
-                            .get_or_init(|| Mutex::new("Sympho".to_string()))
-                            .lock()
-                        {
-                            a.name(name);
-                        }
-
-                        a.url("https://github.com/2vg/sympho");
-
-                        a
-                    });
-                    e.title(track_sympho.title.clone());
-                    e.description(&format!(
-                        "{} / {}",
-                        track_current_position,
-                        if let Some(dur) = track_handle.metadata().duration {
-                            dur_to_hhmmss(dur)
-                        } else {
-                            "Unknown".to_string()
-                        }
-                    ));
-                    if let Some(url) = &track_handle.metadata().source_url {
-                        e.url(url);
-                    }
-                    if let Some(thumb_url) = &track_handle.metadata().thumbnail {
-                        e.thumbnail(thumb_url);
-                    }
-
-                    e
-                });
-
-                m
-            })
-            .await,
-    );
-}
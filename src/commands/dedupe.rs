@@ -0,0 +1,57 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const DEDUPE_USAGE: &str = "example usage: <PREFIX>dedupe -> dedupe the whole queue.
+example usage: <PREFIX>dedupe 1 10 -> dedupe No.1 ~ No.10 songs.
+example usage: <PREFIX>dedupe @user -> dedupe only the songs requested by the user.
+example usage: <PREFIX>dedupe @user 1 10 -> both of them.";
+
+#[command]
+#[only_in(guilds)]
+#[description("Remove the songs that are already on the queue.\nusage: <PREFIX>dedupe [@user] [<start> <end>]")]
+async fn dedupe(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let filter = if let Some(filter) = parse_queue_filter(&mut args) {
+        filter
+    } else {
+        return Err(SymphoError::Usage(DEDUPE_USAGE.to_string()).into());
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            if let Some(len) = sympho_data.dedupe_tracks(&filter) {
+                check_msg(
+                    msg.reply(&ctx.http, format!("Removed {} duplicated songs.", len))
+                        .await,
+                );
+            } else {
+                return Err(SymphoError::Usage(format!(
+                    "the queue has {} songs.\n{}",
+                    sympho_data.queue.len(),
+                    DEDUPE_USAGE
+                ))
+                .into());
+            }
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
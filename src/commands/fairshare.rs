@@ -0,0 +1,58 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const FAIRSHARE_USAGE: &str = "usage: <PREFIX>fairshare on or <PREFIX>fairshare off";
+
+#[command]
+#[aliases("fair")]
+#[only_in(guilds)]
+#[description("Enable/Disable fair share. the queue is ordered by turns of the users who requested the songs.\nusage: <PREFIX>fairshare on")]
+async fn fairshare(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let fair_share = match args.single::<String>().as_deref() {
+        Ok("on") => true,
+        Ok("off") => false,
+        _ => return Err(SymphoError::Usage(FAIRSHARE_USAGE.to_string()).into()),
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            sympho_data.fair_share = fair_share;
+            if fair_share {
+                sympho_data.fair_share_order();
+            }
+
+            check_msg(
+                msg.reply(
+                    ctx,
+                    if fair_share {
+                        "Enabled fair share, the queue is ordered by turns of the users."
+                    } else {
+                        "Disabled fair share."
+                    },
+                )
+                .await,
+            );
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
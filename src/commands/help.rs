@@ -1,5 +1,6 @@
 use crate::define::*;
 use crate::import::*;
+use crate::settings::*;
 
 #[command]
 #[only_in(guilds)]
@@ -16,14 +17,7 @@ async fn help(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 
 async fn say_help_with_embed(msg: &Message, ctx: &Context, cmd_name: &str) {
     let cmd_group = &GENERAL_GROUP.options;
-    let prefix = if let Ok(sympho_prefix) = SYMPHO_PREFIX
-        .get_or_init(|| Mutex::new("!".to_string()))
-        .lock()
-    {
-        (*sympho_prefix).clone()
-    } else {
-        "!".to_string()
-    };
+    let prefix = prefix_of(ctx, msg.guild_id.map(|guild_id| guild_id.0)).await;
 
     for cmd in cmd_group.commands {
         if cmd
@@ -37,27 +31,7 @@ async fn say_help_with_embed(msg: &Message, ctx: &Context, cmd_name: &str) {
                 msg.channel_id
                     .send_message(&ctx.http, |m| {
                         m.embed(|e| {
-                            e.author(|a| {
-                                if let Ok(icon) = SYMPHO_ICON
-                                    .get_or_init(|| {
-                                        Mutex::new(
-                                            "https://cdn.discordapp.com/embed/avatars/0.png"
-                                                .to_string(),
-                                        )
-                                    })
-                                    .lock()
-                                {
-                                    a.icon_url(icon);
-                                }
-                                if let Ok(name) = SYMPHO_NAME
-                                    .get_or_init(|| Mutex::new("Sympho".to_string()))
-                                    .lock()
-                                {
-                                    a.name(name);
-                                }
-                                a.url("https://github.com/2vg/sympho");
-                                a
-                            });
+                            e.author(sympho_author);
                             e.title(format!("{}{}", prefix, cmd_name));
                             e.description(cmd.options.desc.unwrap_or("description is empty"));
                             if cmd.options.names.len() > 1 {
@@ -85,14 +59,7 @@ async fn say_help_with_embed(msg: &Message, ctx: &Context, cmd_name: &str) {
 
 async fn say_help_list_with_embed(msg: &Message, ctx: &Context) {
     let cmd_group = &GENERAL_GROUP.options;
-    let prefix = if let Ok(sympho_prefix) = SYMPHO_PREFIX
-        .get_or_init(|| Mutex::new("!".to_string()))
-        .lock()
-    {
-        (*sympho_prefix).clone()
-    } else {
-        "!".to_string()
-    };
+    let prefix = prefix_of(ctx, msg.guild_id.map(|guild_id| guild_id.0)).await;
 
     let cmds = cmd_group
         .commands
@@ -106,29 +73,7 @@ async fn say_help_list_with_embed(msg: &Message, ctx: &Context) {
         msg.channel_id
             .send_message(&ctx.http, |m| {
                 m.embed(|e| {
-                    e.author(|a| {
-                        if let Ok(icon) = SYMPHO_ICON
-                            .get_or_init(|| {
-                                Mutex::new(
-                                    "https://cdn.discordapp.com/embed/avatars/0.png".to_string(),
-                                )
-                            })
-                            .lock()
-                        {
-                            a.icon_url(icon);
-                        }
-
-                        if let Ok(name) = SYMPHO_NAME
-                            .get_or_init(|| Mutex::new("Sympho".to_string()))
-                            .lock()
-                        {
-                            a.name(name);
-                        }
-
-                        a.url("https://github.com/2vg/sympho");
-
-                        a
-                    });
+                    e.author(sympho_author);
                     e.description(
                         [
                             "This bot made by ururu#5687.",
@@ -0,0 +1,65 @@
+use crate::commands::queue::*;
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+#[command]
+#[aliases("hist")]
+#[only_in(guilds)]
+#[description("Shows a list of songs played recently, the latest is first.")]
+async fn history(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+
+    let data = ctx.data.read().await;
+    if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+        let sympho_global = sympho_global_mutex.read().await;
+        let history = if let Some(sympho_data) = sympho_global.get(&guild_id.0) {
+            &sympho_data.history
+        } else {
+            check_msg(msg.reply(ctx, "No songs played yet.").await);
+            return Ok(());
+        };
+
+        if history.is_empty() {
+            check_msg(msg.reply(ctx, "No songs played yet.").await);
+            return Ok(());
+        }
+
+        say_history_with_embed(msg, ctx, history).await;
+    }
+
+    Ok(())
+}
+
+async fn say_history_with_embed(msg: &Message, ctx: &Context, history: &VecDeque<TrackSympho>) {
+    // the latest is No.1, the titles are cut the same way as the queue so the field fits
+    let entries = history
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, track)| (i + 1, track))
+        .collect::<Vec<_>>();
+    let (titles, durations) = page_fields(&entries);
+
+    check_msg(
+        msg.channel_id
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    e.author(sympho_author);
+                    e.title("Recently played songs");
+                    e.description(format!("{} songs, up to {}", history.len(), HISTORY_LEN));
+                    e.field("Song title", titles, true);
+                    e.field("Song Length", durations, true);
+                    e
+                });
+
+                m
+            })
+            .await,
+    );
+}
@@ -1,6 +1,8 @@
 use crate::define::*;
-use crate::events::*;
+use crate::error::*;
 use crate::import::*;
+use crate::player::*;
+use crate::settings::*;
 
 #[command]
 #[only_in(guilds)]
@@ -9,28 +11,9 @@ async fn join(ctx: &Context, msg: &Message) -> CommandResult {
     let guild = if let Some(g) = msg.guild(&ctx.cache) {
         g
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot could'nt get the guild information, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoGuild.into());
     };
     let guild_id = guild.id;
-    let manager = if let Some(m) = songbird::get(ctx).await {
-        m
-    } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
-    };
 
     let connect_to = if let Some(c) = guild
         .voice_states
@@ -39,51 +22,33 @@ async fn join(ctx: &Context, msg: &Message) -> CommandResult {
     {
         c
     } else {
-        check_msg(
-                msg.reply(ctx, "The bot could'nt get the voice channel information, please contact to developer. >_<!")
-                    .await,
-            );
-        return Ok(());
+        return Err(SymphoError::UserNotInVoice.into());
     };
 
-    let (handle_lock, success) = manager.join(guild_id, connect_to).await;
-
-    if let Ok(_channel) = success {
-        // let chan_id = msg.channel_id;
-
-        // let send_http = ctx.http.clone();
+    // announcements go to the bound channel if the guild has one
+    let chan_id = bound_channel_of(ctx, guild_id.0)
+        .await
+        .unwrap_or(msg.channel_id);
 
-        let mut handle = handle_lock.lock().await;
+    connect(ctx, guild_id, connect_to, chan_id).await?;
 
-        handle.add_global_event(
-            Event::Track(TrackEvent::Play),
-            TrackStartNotifier {
-                data: ctx.data.clone(),
-                key: guild_id.0,
-                // chan_id,
-                // http: send_http.clone(),
-            },
-        );
-
-        handle.add_global_event(
-            Event::Track(TrackEvent::End),
-            TrackEndNotifier {
-                handler: handle_lock.clone(),
-                data: ctx.data.clone(),
-                key: guild_id.0,
-                // chan_id,
-                // http: send_http.clone(),
-            },
-        );
-
-        check_msg(
-            msg.channel_id
-                .say(&ctx.http, &format!("Joined {}", connect_to.mention()))
-                .await,
-        );
-    } else {
-        check_msg(msg.reply(&ctx.http, "Error joining the channel.").await);
+    {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+            // a new session starts with the default volume of the guild
+            if sympho_data.current.is_none() {
+                sympho_data.volume = sympho_data.settings.volume;
+            }
+        }
     }
 
+    check_msg(
+        msg.channel_id
+            .say(&ctx.http, &format!("Joined {}", connect_to.mention()))
+            .await,
+    );
+
     Ok(())
 }
@@ -0,0 +1,103 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+use crate::settings::*;
+
+const LIMITS_USAGE: &str = "example usage: <PREFIX>limits -> show the current limits.
+example usage: <PREFIX>limits queue 100 -> up to 100 songs on the queue.
+example usage: <PREFIX>limits duration 1:00:00 -> reject songs longer than 1 hour.
+example usage: <PREFIX>limits user 10 -> up to 10 songs on the queue per user.
+example usage: <PREFIX>limits live off -> reject live streams.
+`off` removes the limit of queue, duration and user.";
+
+// `off` or the value parsed by `parse`
+fn parse_limit<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Option<T>> {
+    if value == "off" {
+        Some(None)
+    } else {
+        parse(value).map(Some)
+    }
+}
+
+fn describe_limits(limits: &QueueLimits) -> String {
+    let or_off = |limit: Option<String>| limit.unwrap_or_else(|| "off".to_string());
+
+    format!(
+        "queue: {}\nduration: {}\nuser: {}\nlive: {}",
+        or_off(limits.max_queue_len.map(|max| format!("{} songs", max))),
+        or_off(limits.max_duration.map(dur_to_hhmmss)),
+        or_off(limits.max_per_user.map(|max| format!("{} songs", max))),
+        if limits.allow_live { "on" } else { "off" }
+    )
+}
+
+#[command]
+#[only_in(guilds)]
+#[description("Show or set the limits of the queue.\nusage: <PREFIX>limits, <PREFIX>limits <queue|duration|user|live> <value|off>")]
+async fn limits(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let change = if args.len() == 0 {
+        None
+    } else if let (2, Ok(name), Ok(value)) =
+        (args.len(), args.single::<String>(), args.single::<String>())
+    {
+        Some((name, value))
+    } else {
+        return Err(SymphoError::Usage(LIMITS_USAGE.to_string()).into());
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+
+    let data = ctx.data.read().await;
+    if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+        let limits = &mut sympho_data.settings.limits;
+        let changing = change.is_some();
+
+        if let Some((name, value)) = change {
+            let changed = match name.as_str() {
+                "queue" => parse_limit(&value, |v| v.parse::<usize>().ok())
+                    .map(|max| limits.max_queue_len = max),
+                "duration" => {
+                    parse_limit(&value, parse_timestamp).map(|max| limits.max_duration = max)
+                }
+                "user" => parse_limit(&value, |v| v.parse::<usize>().ok())
+                    .map(|max| limits.max_per_user = max),
+                "live" => match value.as_str() {
+                    "on" => Some(true),
+                    "off" => Some(false),
+                    _ => None,
+                }
+                .map(|allow| limits.allow_live = allow),
+                _ => None,
+            };
+
+            if changed.is_none() {
+                return Err(SymphoError::Usage(LIMITS_USAGE.to_string()).into());
+            }
+        }
+
+        let reply = if changing {
+            format!(
+                "Queue limits, the songs already on the queue are kept.\n{}",
+                describe_limits(limits)
+            )
+        } else {
+            format!("Queue limits\n{}", describe_limits(limits))
+        };
+        drop(sympho_global);
+
+        check_msg(msg.reply(&ctx.http, reply).await);
+
+        if changing {
+            save_settings(sympho_global_mutex).await;
+        }
+    }
+
+    Ok(())
+}
@@ -1,78 +1,57 @@
 use crate::define::*;
+use crate::error::*;
 use crate::import::*;
 
+const LOOP_USAGE: &str = "usage: <PREFIX>loop off, <PREFIX>loop track or <PREFIX>loop queue
+track repeats the current playing song, queue repeats the whole queue.";
+
 #[command]
 #[aliases("loop")]
 #[only_in(guilds)]
-#[description("Enable/Disable loop the current playing song.\nusage: <PREFIX>loop on")]
+#[description("Set the loop mode. off, track(loop the current playing song) or queue(loop the whole queue).\nusage: <PREFIX>loop queue")]
 async fn looping(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    let looping = if let Ok(arg) = args.single::<String>() {
-        if arg != "on" && arg != "off" {
-            return Ok(());
-        };
-        arg
+    let loop_mode = if let Ok(loop_mode) = args.single::<LoopMode>() {
+        loop_mode
     } else {
-        return Ok(());
+        return Err(SymphoError::Usage(LOOP_USAGE.to_string()).into());
     };
 
     let guild = if let Some(g) = msg.guild(&ctx.cache) {
         g
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot could'nt get the guild information, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoGuild.into());
     };
     let guild_id = guild.id;
     let manager = if let Some(m) = songbird::get(ctx).await {
         m
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoSongbird.into());
     };
 
     if let Some(_handler_lock) = manager.get(guild_id) {
         let data = ctx.data.read().await;
         if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
             let mut sympho_global = sympho_global_mutex.write().await;
-            let sympho_data = sympho_global.entry(guild_id.0).or_insert(SymphoData {
-                volume: 1.0,
-                ..Default::default()
-            });
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            sympho_data.loop_mode = loop_mode;
 
+            // the next tracks get the mode when they start, only the current one is set here
             if let Some((current, _)) = &sympho_data.current {
-                if looping == "on" {
-                    if let Ok(_) = current.enable_loop() {
-                        check_msg(
-                            msg.reply(ctx, "Enabled loop the current playing song.")
-                                .await,
-                        );
-                    }
+                if loop_mode == LoopMode::Track {
+                    current.enable_loop()?;
                 } else {
-                    if let Ok(_) = current.disable_loop() {
-                        check_msg(
-                            msg.reply(ctx, "Disabled loop the current playing song.")
-                                .await,
-                        );
-                    }
+                    current.disable_loop()?;
                 }
             }
+
+            check_msg(
+                msg.reply(ctx, format!("Loop mode is now `{}`.", loop_mode))
+                    .await,
+            );
         }
     } else {
-        check_msg(
-            msg.reply(ctx, "The bot is not in a voice channel. >_<!")
-                .await,
-        );
+        return Err(SymphoError::NotInVoice.into());
     }
 
     Ok(())
@@ -1,25 +1,63 @@
+pub mod autoplay;
+pub mod clear;
+pub mod config;
 pub mod current;
+pub mod dedupe;
+pub mod fairshare;
 pub mod help;
+pub mod history;
 pub mod join;
 pub mod leave;
+pub mod limits;
 pub mod looping;
+pub mod move_track;
 pub mod pause;
+pub mod permission;
 pub mod play;
+pub mod playlist;
+pub mod playnext;
+pub mod previous;
 pub mod queue;
+pub mod remove;
+pub mod replay;
 pub mod resume;
+pub mod reverse;
+pub mod seek;
+pub mod shuffle;
 pub mod skip;
 pub mod stop;
+pub mod swap;
 pub mod volume;
+pub mod voteskip;
 
+pub use autoplay::*;
+pub use clear::*;
+pub use config::*;
 pub use current::*;
+pub use dedupe::*;
+pub use fairshare::*;
 pub use help::*;
+pub use history::*;
 pub use join::*;
 pub use leave::*;
+pub use limits::*;
 pub use looping::*;
+pub use move_track::*;
 pub use pause::*;
+pub use permission::*;
 pub use play::*;
+pub use playlist::*;
+pub use playnext::*;
+pub use previous::*;
 pub use queue::*;
+pub use remove::*;
+pub use replay::*;
 pub use resume::*;
+pub use reverse::*;
+pub use seek::*;
+pub use shuffle::*;
 pub use skip::*;
 pub use stop::*;
+pub use swap::*;
 pub use volume::*;
+pub use voteskip::*;
@@ -0,0 +1,60 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const MOVE_USAGE: &str = "example usage: <PREFIX>move 5 1 -> move No.5 song to No.1.";
+
+#[command]
+#[aliases("move", "mv")]
+#[only_in(guilds)]
+#[description("Move the song on the queue to specified position.\nusage: <PREFIX>move 5 1")]
+async fn move_track(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (from, to) = if let (2, Ok(from), Ok(to)) =
+        (args.len(), args.single::<usize>(), args.single::<usize>())
+    {
+        (from, to)
+    } else {
+        return Err(SymphoError::Usage(MOVE_USAGE.to_string()).into());
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            if sympho_data.move_track(from, to) {
+                check_msg(
+                    msg.reply(
+                        &ctx.http,
+                        format!("Moved `{}` to No.{}.", sympho_data.queue[to - 1].title, to),
+                    )
+                    .await,
+                );
+            } else {
+                return Err(SymphoError::Usage(format!(
+                    "the queue has {} songs.\n{}",
+                    sympho_data.queue.len(),
+                    MOVE_USAGE
+                ))
+                .into());
+            }
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use crate::define::*;
+use crate::error::*;
 use crate::import::*;
 
 #[command]
@@ -8,47 +9,29 @@ async fn pause(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
     let guild = if let Some(g) = msg.guild(&ctx.cache) {
         g
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot could'nt get the guild information, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoGuild.into());
     };
     let guild_id = guild.id;
     let manager = if let Some(m) = songbird::get(ctx).await {
         m
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoSongbird.into());
     };
 
     if let Some(_handler_lock) = manager.get(guild_id) {
         let data = ctx.data.read().await;
         if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
             let mut sympho_global = sympho_global_mutex.write().await;
-            let sympho_data = sympho_global.entry(guild_id.0).or_insert(SymphoData {
-                volume: 1.0,
-                ..Default::default()
-            });
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
 
             if let Some((current, _)) = &sympho_data.current {
                 current.pause()?;
+            } else {
+                return Err(SymphoError::NotPlaying.into());
             }
         }
     } else {
-        check_msg(
-            msg.reply(ctx, "The bot is not in a voice channel. >_<!")
-                .await,
-        );
+        return Err(SymphoError::NotInVoice.into());
     }
 
     Ok(())
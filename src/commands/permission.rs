@@ -0,0 +1,98 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+use crate::permission::*;
+use crate::settings::*;
+
+const PERMISSION_USAGE: &str = "example usage: <PREFIX>permission -> show the settings.
+example usage: <PREFIX>permission dj add DJUser -> users with the role can use the dj commands.
+example usage: <PREFIX>permission admin remove @Staff -> remove the role from the admin roles.
+example usage: <PREFIX>permission command play everyone -> everyone can use play.
+example usage: <PREFIX>permission command play default -> back to the default level of play.
+example usage: <PREFIX>permission alone off -> don't allow the dj commands when alone with the bot.";
+
+fn edit_permissions(permissions: &mut Permissions, args: &[String]) -> Option<()> {
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+
+    match args.as_slice() {
+        [kind @ "dj", action, role @ ..] | [kind @ "admin", action, role @ ..]
+            if !role.is_empty() =>
+        {
+            let roles = if *kind == "dj" {
+                &mut permissions.dj_roles
+            } else {
+                &mut permissions.admin_roles
+            };
+            let role = parse_role(&role.join(" "));
+
+            match *action {
+                "add" => {
+                    if !roles.contains(&role) {
+                        roles.push(role);
+                    }
+                }
+                "remove" => roles.retain(|r| *r != role),
+                _ => return None,
+            }
+        }
+        ["command", name, "default"] => {
+            let name = canonical_command_name(name)?;
+            permissions.command_levels.remove(name);
+        }
+        ["command", name, level] => {
+            let name = canonical_command_name(name)?;
+            let level = level.parse::<PermissionLevel>().ok()?;
+            // nobody could change it back
+            if name == "permission" && level != PermissionLevel::Admin {
+                return None;
+            }
+            permissions.command_levels.insert(name.to_string(), level);
+        }
+        ["alone", "on"] => permissions.allow_when_alone = true,
+        ["alone", "off"] => permissions.allow_when_alone = false,
+        _ => return None,
+    }
+
+    Some(())
+}
+
+#[command]
+#[aliases("perm")]
+#[only_in(guilds)]
+#[description("Show or edit who can use the commands.\nusage: <PREFIX>permission, <PREFIX>permission <dj|admin> <add|remove> <role>, <PREFIX>permission command <name> <everyone|dj|admin|default>, <PREFIX>permission alone <on|off>")]
+async fn permission(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let all_args = args
+        .iter::<String>()
+        .map(|arg| arg.unwrap_or_default())
+        .collect::<Vec<String>>();
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+
+    let data = ctx.data.read().await;
+    if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+        if !all_args.is_empty()
+            && edit_permissions(&mut sympho_data.settings.permissions, &all_args).is_none()
+        {
+            return Err(SymphoError::Usage(PERMISSION_USAGE.to_string()).into());
+        }
+
+        let reply = sympho_data.settings.permissions.describe();
+        drop(sympho_global);
+
+        check_msg(msg.reply(&ctx.http, reply).await);
+
+        if !all_args.is_empty() {
+            save_settings(sympho_global_mutex).await;
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
 use crate::define::*;
+use crate::error::*;
 use crate::import::*;
+use crate::player::*;
+use crate::resolver::*;
+use crate::watchdog::*;
 
 const SHUFFLE_WORDS: &[&str] = &["shuffle", "random"];
 
@@ -7,22 +11,27 @@ const SHUFFLE_WORDS: &[&str] = &["shuffle", "random"];
 #[aliases("p")]
 #[only_in(guilds)]
 #[description("Start to play music. supported some site, support playlist, file upload\nusage: <PREFIX>play https://youtube.com/watch?v=... or, just type keywords then bot will play the first result from youtube, or play with file upload.\nif passed playlist url and passed it with \"shuffle\" or \"random\" as last argments, playlist queue will be shuffled.")]
-async fn play(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+async fn play(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    play_url(ctx, msg, args, false).await
+}
+
+// Shared by `play` and `playnext`, `at_head` puts the songs in front of the queue
+pub async fn play_url(
+    ctx: &Context,
+    msg: &Message,
+    mut args: Args,
+    at_head: bool,
+) -> CommandResult {
     let all_args = args
         .iter::<String>()
         .map(|arg| arg.unwrap_or_default())
         .collect::<Vec<String>>();
 
     if all_args.len() == 0 {
-        check_msg(
-            msg.reply(
-                &ctx.http,
-                "Must provide a URL to a video or audio, keywords, or attachments",
-            )
-            .await,
-        );
-
-        return Ok(());
+        return Err(SymphoError::Usage(
+            "Must provide a URL to a video or audio, keywords, or attachments".to_string(),
+        )
+        .into());
     }
 
     let url = if Url::parse(all_args.first().unwrap_or(&String::new()).as_str()).is_ok() {
@@ -48,178 +57,172 @@ async fn play(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     let guild = if let Some(g) = msg.guild(&ctx.cache) {
         g
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot could'nt get the guild information, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoGuild.into());
     };
     let guild_id = guild.id;
     let manager = if let Some(m) = songbird::get(ctx).await {
         m
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoSongbird.into());
     };
 
     if let Some(handler_lock) = manager.get(guild_id) {
-        let mut handler = handler_lock.lock().await;
+        enqueue(
+            ctx,
+            msg,
+            &handler_lock,
+            guild_id.0,
+            url,
+            enable_shuffle,
+            at_head,
+        )
+        .await;
 
-        let len = enqueue(ctx, guild_id.0, url.clone(), enable_shuffle).await;
+        Ok(())
+    } else {
+        Err(SymphoError::NotInVoice.into())
+    }
+}
 
-        if len != 0 {
+// Tell how many songs were added, start playing, then report the songs that were not added
+pub async fn say_enqueued(
+    ctx: &Context,
+    msg: &Message,
+    handler_lock: &Arc<serenity::prelude::Mutex<Call>>,
+    key: u64,
+    enqueued: Result<Enqueued, YtdlError>,
+) -> Result<(), SymphoError> {
+    if let Ok(enqueued) = &enqueued {
+        if enqueued.added != 0 {
             check_msg(
-                msg.reply(&ctx.http, format!("Added {} song to queue.", len))
-                    .await,
+                msg.reply(
+                    &ctx.http,
+                    format!("Added {} song to queue.", enqueued.added),
+                )
+                .await,
             );
+        }
+    }
 
-            dequeue(&mut handler, ctx, guild_id.0).await;
+    // always try, other entries may have been waiting behind our placeholder
+    dequeue(handler_lock, ctx, key, msg.channel_id).await;
+
+    match enqueued {
+        Ok(enqueued) if !enqueued.rejected.is_empty() => {
+            let limits = limits_of(ctx, key).await;
+            Err(SymphoError::Rejected(describe_rejections(
+                &enqueued.rejected,
+                &limits,
+            )))
         }
-    } else {
-        check_msg(
-            msg.reply(ctx, "The bot is not in a voice channel. >_<!")
-                .await,
-        );
+        Ok(enqueued) if enqueued.added == 0 => Err(SymphoError::NoSongs),
+        Err(why) => Err(SymphoError::Extract(why)),
+        _ => Ok(()),
     }
+}
 
-    Ok(())
+// Queue the tracks of `url`, the command replies at once and doesn't wait for them.
+// The lookup runs in its own task, so the queue stays usable meanwhile.
+pub async fn enqueue(
+    ctx: &Context,
+    msg: &Message,
+    handler_lock: &Arc<serenity::prelude::Mutex<Call>>,
+    key: u64,
+    url: String,
+    enable_shuffle: bool,
+    at_head: bool,
+) {
+    let lookup = lookup_tracks(url.clone(), enable_shuffle);
+    enqueue_lookup(ctx, msg, handler_lock, key, url, at_head, lookup).await
 }
 
-pub async fn enqueue(ctx: &Context, key: u64, url: String, enable_shuffle: bool) -> usize {
-    let data = ctx.data.read().await;
-    if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
-        let mut sympho_global = sympho_global_mutex.write().await;
-        let sympho_data = sympho_global.entry(key).or_insert(SymphoData {
-            volume: 1.0,
-            ..Default::default()
-        });
-
-        if is_file_url(&url) {
-            if let Ok(info) = get_audio_file_info(&url) {
-                sympho_data.queue.push(TrackSympho {
-                    url: url.clone(),
-                    title: info.0,
-                    thumb: None,
-                    duration: info.1,
-                });
-                sympho_data.queue_duration += info.1;
-            } else {
-                let dur = Duration::new(0, 0);
-                sympho_data.queue.push(TrackSympho {
-                    url: url.clone(),
-                    title: "Unknown".to_string(),
-                    thumb: None,
-                    duration: dur,
-                });
-                sympho_data.queue_duration += dur;
-            };
-
-            return 1;
+// Same as `enqueue`, but the tracks come from `lookup`(e.g. the songs of a saved playlist).
+// when the lookup is done, the result is posted by `say_enqueued` and the next song is started
+pub async fn enqueue_lookup<F>(
+    ctx: &Context,
+    msg: &Message,
+    handler_lock: &Arc<serenity::prelude::Mutex<Call>>,
+    key: u64,
+    url: String,
+    at_head: bool,
+    lookup: F,
+) where
+    F: Future<Output = Result<Vec<TrackSympho>, YtdlError>> + Send + 'static,
+{
+    let sympho_global_mutex = {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            sympho_global_mutex.clone()
         } else {
-            let output = YoutubeDl::new(&url)
-                .flat_playlist(true)
-                .socket_timeout(5)
-                .run();
-
-            if let Ok(yt) = output {
-                match yt {
-                    YoutubeDlOutput::Playlist(yt_pl) => {
-                        let entries = yt_pl.entries.unwrap_or(vec![]);
-                        let mut track_vec = Vec::new();
-                        let mut list_count = 0usize;
-
-                        for sv in entries {
-                            let url = sv.url.unwrap_or("".to_string());
-                            if url != "" {
-                                let dur = if let Some(dur) = sv.duration {
-                                    Duration::from_secs_f64(dur.as_f64().unwrap_or(0.0))
-                                } else {
-                                    Duration::new(0, 0)
-                                };
-                                track_vec.push(TrackSympho {
-                                    url: format!("https://www.youtube.com/watch?v={}", url),
-                                    title: sv.title,
-                                    thumb: sv.thumbnail,
-                                    duration: dur,
-                                });
-                                sympho_data.queue_duration += dur;
-                                list_count += 1;
-                            };
-                        }
-
-                        if enable_shuffle {
-                            let mut rng = rand::thread_rng();
-                            track_vec.shuffle(&mut rng);
-                        }
-
-                        sympho_data.queue.extend_from_slice(&track_vec);
-
-                        return list_count;
-                    }
-                    YoutubeDlOutput::SingleVideo(yt_sv) => {
-                        let url = yt_sv.webpage_url.unwrap_or("".to_string());
-                        if url != "" {
-                            let dur = if let Some(dur) = yt_sv.duration {
-                                Duration::from_secs_f64(dur.as_f64().unwrap_or(0.0))
-                            } else {
-                                Duration::new(0, 0)
-                            };
-                            sympho_data.queue.push(TrackSympho {
-                                url: url.clone(),
-                                title: yt_sv.title,
-                                thumb: yt_sv.thumbnail,
-                                duration: dur,
-                            });
-                            sympho_data.queue_duration += dur;
-                            return 1;
-                        };
-                    }
-                };
-            }
+            return;
         }
-    }
+    };
+
+    let resolution = spawn_resolution(
+        sympho_global_mutex,
+        key,
+        url.clone(),
+        Some(msg.author.id),
+        at_head,
+        lookup,
+    )
+    .await;
+
+    check_msg(
+        msg.reply(
+            &ctx.http,
+            format!(
+                "Queued `{}`(resolving...).",
+                url.strip_prefix("ytsearch1:").unwrap_or(&url)
+            ),
+        )
+        .await,
+    );
+
+    let ctx = ctx.clone();
+    let msg = msg.clone();
+    let handler_lock = handler_lock.clone();
+    tokio::spawn(async move {
+        let enqueued = resolution.await.unwrap_or_else(|_| Ok(Enqueued::default()));
 
-    0
+        if let Err(why) = say_enqueued(&ctx, &msg, &handler_lock, key, enqueued).await {
+            say_sympho_error(&ctx.http, msg.channel_id, &why).await;
+        }
+    });
 }
 
-pub async fn dequeue(handler: &mut Call, ctx: &Context, key: u64) {
+async fn limits_of(ctx: &Context, key: u64) -> QueueLimits {
     let data = ctx.data.read().await;
     if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
-        let mut sympho_global = sympho_global_mutex.write().await;
-        let sympho_data = sympho_global.entry(key).or_insert(SymphoData {
-            volume: 1.0,
-            ..Default::default()
-        });
-
-        if sympho_data.queue.len() == 0 || sympho_data.current.is_some() {
-            return;
+        if let Some(sympho_data) = sympho_global_mutex.read().await.get(&key) {
+            return sympho_data.settings.limits.clone();
         }
+    }
 
-        let track_sympho = sympho_data.queue[0].clone();
-        let url = track_sympho.url.clone();
-
-        sympho_data.queue.remove(0);
+    QueueLimits::default()
+}
 
-        let source = if let Ok(source) = get_source(url).await {
-            source
+// Start playing if nothing is playing yet
+pub async fn dequeue(
+    handler_lock: &Arc<serenity::prelude::Mutex<Call>>,
+    ctx: &Context,
+    key: u64,
+    chan_id: ChannelId,
+) {
+    let sympho_global_mutex = {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            sympho_global_mutex.clone()
         } else {
             return;
-        };
+        }
+    };
 
-        sympho_data.queue_duration -= track_sympho.duration;
+    let dropped = start_next(handler_lock, &sympho_global_mutex, key).await;
+    say_dropped_tracks(&ctx.http, chan_id, &dropped).await;
 
-        sympho_data.current = Some((
-            play_from_source(handler, source, sympho_data.volume),
-            track_sympho,
-        ));
+    // every song may have failed to open, then nothing is playing
+    if !dropped.is_empty() {
+        watch_idle(&ctx.data, &ctx.http, key).await;
     }
 }
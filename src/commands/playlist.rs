@@ -0,0 +1,462 @@
+use crate::commands::play::*;
+use crate::commands::queue::*;
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+use crate::permission::*;
+use crate::playlist::*;
+use crate::resolver::*;
+
+const PLAYLIST_USAGE: &str = "example usage: <PREFIX>playlist save chill -> save the current song and the queue as `chill` of the server.
+example usage: <PREFIX>playlist save --mine chill -> save it as your own `chill`, you can load it in any server.
+example usage: <PREFIX>playlist load chill shuffle -> add the songs of `chill` to the queue(shuffled with `shuffle`).
+example usage: <PREFIX>playlist list @user -> show the playlists of the server(only the ones of @user), `--mine` for your own playlists.
+example usage: <PREFIX>playlist show chill 2 -> show the songs of `chill`, 10 songs per page(page 2).
+example usage: <PREFIX>playlist add chill https://youtube.com/watch?v=... -> add a song(or keywords) to `chill`.
+example usage: <PREFIX>playlist remove chill 3 -> remove No.3 song from `chill`.
+example usage: <PREFIX>playlist delete chill -> delete `chill`.
+`--mine` works with every action. only the owner of the playlist(and admins for the ones of the server) can edit it, everyone can use list and show.";
+
+// Picks the playlists of the user instead of the ones of the server, anywhere in the arguments
+pub const MINE_FLAG: &str = "--mine";
+
+// Messages are 2000 chars at most
+const MESSAGE_LIMIT: usize = 2000;
+
+fn usage() -> SymphoError {
+    SymphoError::Usage(PLAYLIST_USAGE.to_string())
+}
+
+// Which playlist the replies talk about
+fn scope_name(scope: PlaylistScope) -> &'static str {
+    match scope {
+        PlaylistScope::Guild(_) => "the playlist of the server",
+        PlaylistScope::User(_) => "your playlist",
+    }
+}
+
+#[command]
+#[aliases("pl")]
+#[only_in(guilds)]
+#[description("Save the queue as a playlist of the server(or your own with `--mine`) and load it later.\nusage: <PREFIX>playlist <save|load|delete> <name>, <PREFIX>playlist show <name> Option<page>, <PREFIX>playlist list Option<@user>, <PREFIX>playlist add <name> <url or keywords>, <PREFIX>playlist remove <name> <number>\nloaded songs go through the same checks as play. `--mine` picks your own playlists, they can be loaded in any server. only the owner of the playlist and admins(not for your own ones) can edit it.")]
+async fn playlist(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+
+    let scope = if args.raw().any(|arg| arg == MINE_FLAG) {
+        PlaylistScope::User(msg.author.id.0)
+    } else {
+        PlaylistScope::Guild(guild_id.0)
+    };
+    let rest = args
+        .raw()
+        .filter(|arg| *arg != MINE_FLAG)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut args = Args::new(&rest, &[Delimiter::Single(' ')]);
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+
+    let (sympho_global_mutex, playlist_map) = {
+        let data = ctx.data.read().await;
+        match (
+            data.get::<SymphoGlobal>().cloned(),
+            data.get::<SymphoPlaylists>().cloned(),
+        ) {
+            (Some(sympho_global_mutex), Some(playlist_map)) => (sympho_global_mutex, playlist_map),
+            _ => return Ok(()),
+        }
+    };
+
+    if action == "list" {
+        let owner = args
+            .current()
+            .filter(|arg| arg.starts_with("<@"))
+            .and_then(|arg| arg.parse::<UserId>().ok());
+        return list_playlists(ctx, msg, &playlist_map, scope, owner).await;
+    }
+
+    let name = if let Some(name) = args
+        .single::<String>()
+        .ok()
+        .and_then(|name| parse_playlist_name(&name))
+    {
+        name
+    } else {
+        return Err(usage().into());
+    };
+
+    let roles = msg
+        .member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+    let is_admin =
+        permissions_of(ctx, guild_id.0)
+            .await
+            .level_of_user(&guild, msg.author.id, &roles)
+            == PermissionLevel::Admin;
+
+    match action.as_str() {
+        "save" => {
+            let tracks = sympho_global_mutex
+                .read()
+                .await
+                .get(&guild_id.0)
+                .map(saved_tracks_of)
+                .unwrap_or_default();
+            if tracks.is_empty() {
+                return Err(SymphoError::NotPlaying.into());
+            }
+
+            let saved = {
+                let mut playlist_store = playlist_map.write().await;
+                let playlists = playlist_store.playlists_mut(scope);
+
+                // overwriting keeps the owner
+                let owner = match playlists.get(&name) {
+                    Some(saved) if !saved.can_edit(scope, msg.author.id, is_admin) => {
+                        return Err(SymphoError::NotPlaylistOwner(name).into());
+                    }
+                    Some(saved) => UserId(saved.owner),
+                    None => msg.author.id,
+                };
+
+                let saved = SavedPlaylist::new(owner, tracks);
+                playlists.insert(name.clone(), saved.clone());
+                saved
+            };
+            save_playlists(&playlist_map, scope).await;
+
+            check_msg(
+                msg.reply(
+                    &ctx.http,
+                    format!(
+                        "Saved {} songs({}) as `{}`, {}.",
+                        saved.tracks.len(),
+                        format_duration(saved.duration()),
+                        name,
+                        scope_name(scope)
+                    ),
+                )
+                .await,
+            );
+        }
+        "load" => {
+            let enable_shuffle = args
+                .current()
+                .map(|arg| arg == "shuffle" || arg == "random")
+                .unwrap_or(false);
+
+            let mut tracks = if let Some(saved) = playlist_map.read().await.get(scope, &name) {
+                saved.tracks.clone()
+            } else {
+                return Err(SymphoError::NoPlaylist(name).into());
+            };
+
+            if enable_shuffle {
+                let mut rng = rand::thread_rng();
+                tracks.shuffle(&mut rng);
+            }
+
+            let manager = if let Some(m) = songbird::get(ctx).await {
+                m
+            } else {
+                return Err(SymphoError::NoSongbird.into());
+            };
+            let handler_lock = if let Some(handler_lock) = manager.get(guild_id) {
+                handler_lock
+            } else {
+                return Err(SymphoError::NotInVoice.into());
+            };
+
+            // the songs are already known, so the lookup just hands them over
+            enqueue_lookup(
+                ctx,
+                msg,
+                &handler_lock,
+                guild_id.0,
+                format!("playlist {}", name),
+                false,
+                async move { Ok(tracks) },
+            )
+            .await;
+        }
+        "show" => {
+            let page = args.single::<usize>().unwrap_or(1).max(1);
+
+            let (description, fields) = {
+                let playlist_store = playlist_map.read().await;
+                let saved = if let Some(saved) = playlist_store.get(scope, &name) {
+                    saved
+                } else {
+                    return Err(SymphoError::NoPlaylist(name).into());
+                };
+
+                let pages = page_count(saved.tracks.len());
+                if page > pages {
+                    return Err(SymphoError::Usage(format!(
+                        "usage: <PREFIX>playlist show {} 1\n`{}` has {} pages, page is 1 first.",
+                        name, name, pages
+                    ))
+                    .into());
+                }
+
+                let description = format!(
+                    "By {}, {} songs({})\nPage {} of {}",
+                    UserId(saved.owner).mention(),
+                    saved.tracks.len(),
+                    format_duration(saved.duration()),
+                    page,
+                    pages
+                );
+                // the same fields as the queue, so a page always fits into the embed
+                let entries = matching_tracks(&saved.tracks, None)
+                    .into_iter()
+                    .skip((page - 1) * QUEUE_PAGE_LEN)
+                    .take(QUEUE_PAGE_LEN)
+                    .collect::<Vec<_>>();
+                let fields = if entries.is_empty() {
+                    None
+                } else {
+                    Some(page_fields(&entries))
+                };
+
+                (description, fields)
+            };
+
+            check_msg(
+                msg.channel_id
+                    .send_message(&ctx.http, |m| {
+                        m.embed(|e| {
+                            e.author(sympho_author);
+                            e.title(format!("Playlist `{}`", name));
+                            e.description(description);
+                            if let Some((titles, durations)) = fields {
+                                e.field("Song title", titles, true);
+                                e.field("Song Length", durations, true);
+                            }
+                            e
+                        });
+                        m
+                    })
+                    .await,
+            );
+        }
+        "delete" => {
+            {
+                let mut playlist_store = playlist_map.write().await;
+                let playlists = playlist_store.playlists_mut(scope);
+
+                match playlists.get(&name) {
+                    None => return Err(SymphoError::NoPlaylist(name).into()),
+                    Some(saved) if !saved.can_edit(scope, msg.author.id, is_admin) => {
+                        return Err(SymphoError::NotPlaylistOwner(name).into());
+                    }
+                    Some(_) => {}
+                }
+
+                playlists.remove(&name);
+            }
+            save_playlists(&playlist_map, scope).await;
+
+            check_msg(
+                msg.reply(
+                    &ctx.http,
+                    format!("Deleted `{}`, {}.", name, scope_name(scope)),
+                )
+                .await,
+            );
+        }
+        "add" => {
+            let query = args.rest().trim().to_string();
+            if query.is_empty() {
+                return Err(usage().into());
+            }
+            let url = if Url::parse(&query).is_ok() {
+                query
+            } else {
+                format!("ytsearch1:{}", query)
+            };
+
+            // a new playlist is owned by the user who adds the first song
+            check_playlist_owner(&playlist_map, scope, &name, msg.author.id, is_admin).await?;
+
+            let tracks = match lookup_tracks(url, false).await {
+                Ok(tracks) if !tracks.is_empty() => tracks,
+                Ok(_) => return Err(SymphoError::NoSongs.into()),
+                Err(why) => return Err(SymphoError::Extract(why).into()),
+            };
+
+            let added = tracks.len();
+            {
+                let mut playlist_store = playlist_map.write().await;
+                // the owner may have changed while youtube-dl was running
+                let saved = playlist_store
+                    .playlists_mut(scope)
+                    .entry(name.clone())
+                    .or_insert_with(|| SavedPlaylist::new(msg.author.id, vec![]));
+                if !saved.can_edit(scope, msg.author.id, is_admin) {
+                    return Err(SymphoError::NotPlaylistOwner(name).into());
+                }
+
+                saved.tracks.extend(tracks.into_iter().map(saved_track));
+            }
+            save_playlists(&playlist_map, scope).await;
+
+            check_msg(
+                msg.reply(&ctx.http, format!("Added {} song to `{}`.", added, name))
+                    .await,
+            );
+        }
+        "remove" => {
+            let index = if let Ok(index) = args.single::<usize>() {
+                index
+            } else {
+                return Err(usage().into());
+            };
+
+            let removed = {
+                let mut playlist_store = playlist_map.write().await;
+                let saved = if let Some(saved) = playlist_store.playlists_mut(scope).get_mut(&name)
+                {
+                    saved
+                } else {
+                    return Err(SymphoError::NoPlaylist(name).into());
+                };
+                if !saved.can_edit(scope, msg.author.id, is_admin) {
+                    return Err(SymphoError::NotPlaylistOwner(name).into());
+                }
+                if index == 0 || index > saved.tracks.len() {
+                    return Err(SymphoError::Usage(format!(
+                        "`{}` has {} songs, number is 1 first.",
+                        name,
+                        saved.tracks.len()
+                    ))
+                    .into());
+                }
+
+                saved.tracks.remove(index - 1)
+            };
+            save_playlists(&playlist_map, scope).await;
+
+            check_msg(
+                msg.reply(
+                    &ctx.http,
+                    format!("Removed `{}` from `{}`.", removed.title, name),
+                )
+                .await,
+            );
+        }
+        _ => return Err(usage().into()),
+    }
+
+    Ok(())
+}
+
+// Error if the playlist exists and the user can't edit it
+async fn check_playlist_owner(
+    playlist_map: &PlaylistMap,
+    scope: PlaylistScope,
+    name: &str,
+    user_id: UserId,
+    is_admin: bool,
+) -> Result<(), SymphoError> {
+    match playlist_map.read().await.get(scope, name) {
+        Some(saved) if !saved.can_edit(scope, user_id, is_admin) => {
+            Err(SymphoError::NotPlaylistOwner(name.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn list_playlists(
+    ctx: &Context,
+    msg: &Message,
+    playlist_map: &PlaylistMap,
+    scope: PlaylistScope,
+    owner: Option<UserId>,
+) -> CommandResult {
+    let mut playlists = playlist_map
+        .read()
+        .await
+        .playlists(scope)
+        .map(|playlists| {
+            playlists
+                .iter()
+                .filter(|(_, saved)| owner.map_or(true, |owner| saved.owner == owner.0))
+                .map(|(name, saved)| {
+                    format!(
+                        "`{}` by {}, {} songs({})",
+                        name,
+                        UserId(saved.owner).mention(),
+                        saved.tracks.len(),
+                        format_duration(saved.duration())
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if playlists.is_empty() {
+        let reply = match scope {
+            PlaylistScope::Guild(_) => "No playlists saved in this server yet.",
+            PlaylistScope::User(_) => "You have no playlists of your own yet.",
+        };
+        check_msg(msg.reply(&ctx.http, reply).await);
+    } else {
+        playlists.sort();
+        check_msg(
+            msg.reply(&ctx.http, join_lines_within(&playlists, MESSAGE_LIMIT))
+                .await,
+        );
+    }
+
+    Ok(())
+}
+
+// The lines that fit into `max` chars, the rest is counted at the end
+fn join_lines_within(lines: &[String], max: usize) -> String {
+    let mut joined = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let more = format!("\n...and {} more", lines.len() - i);
+        let next_len = joined.chars().count() + line.chars().count() + 1;
+        let more_len = if i + 1 < lines.len() {
+            more.chars().count()
+        } else {
+            0
+        };
+        if next_len + more_len > max {
+            return joined + &more;
+        }
+
+        if !joined.is_empty() {
+            joined.push('\n');
+        }
+        joined += line;
+    }
+
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lists_fit_into_message() {
+        let lines = (0..100)
+            .map(|i| format!("`playlist{}` by someone, 10 songs(30m)", i))
+            .collect::<Vec<_>>();
+        let joined = join_lines_within(&lines, MESSAGE_LIMIT);
+        assert!(joined.chars().count() <= MESSAGE_LIMIT);
+        assert!(joined.ends_with("more"));
+
+        let joined = join_lines_within(&lines[..3], MESSAGE_LIMIT);
+        assert_eq!(joined, lines[..3].join("\n"));
+    }
+}
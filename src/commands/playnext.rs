@@ -0,0 +1,10 @@
+use crate::commands::*;
+use crate::import::*;
+
+#[command]
+#[aliases("pn")]
+#[only_in(guilds)]
+#[description("Same as play, but the songs are added to the front of the queue.\nusage: <PREFIX>playnext https://youtube.com/watch?v=... or keywords")]
+async fn playnext(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    play_url(ctx, msg, args, true).await
+}
@@ -0,0 +1,64 @@
+use crate::commands::*;
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+#[command]
+#[aliases("back", "prev")]
+#[only_in(guilds)]
+#[description("Play the last played song again, the music currently playing comes back after it.")]
+async fn previous(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let title = {
+            let data = ctx.data.read().await;
+            if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+                let mut sympho_global = sympho_global_mutex.write().await;
+                let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+                let previous = if let Some(previous) = sympho_data.history.pop_back() {
+                    previous
+                } else {
+                    return Err(SymphoError::Usage("No songs played yet.".to_string()).into());
+                };
+
+                // take the current one out first, so the end event doesn't put it in history
+                if let Some((current_handle, mut current)) = sympho_data.current.take() {
+                    current_handle.stop()?;
+                    current.start = Duration::default();
+                    sympho_data.queue_duration += current.duration;
+                    sympho_data.queue.insert(0, current);
+                }
+
+                let title = previous.title.clone();
+                sympho_data.queue_duration += previous.duration;
+                sympho_data.queue.insert(0, previous);
+                title
+            } else {
+                return Ok(());
+            }
+        };
+
+        check_msg(
+            msg.reply(&ctx.http, format!("Playing `{}` again.", title))
+                .await,
+        );
+
+        dequeue(&handler_lock, ctx, guild_id.0, msg.channel_id).await;
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
@@ -1,143 +1,436 @@
 use crate::define::*;
+use crate::error::*;
+use crate::hooks::*;
 use crate::import::*;
 
+// Songs on one page of the queue
+pub const QUEUE_PAGE_LEN: usize = 10;
+// Embed field values are 1024 chars at most
+const FIELD_LIMIT: usize = 1024;
+// The search words go into the custom id of the buttons, which is 100 chars at most
+const MAX_SEARCH_LEN: usize = 64;
+
+// Custom ids of the page buttons start with this, followed by `<first|prev|next|last>:<page>:<search>`
+pub const QUEUE_BUTTON_PREFIX: &str = "sympho:queue:";
+
 #[command]
 #[aliases("q")]
 #[only_in(guilds)]
-#[description("Shows a list of songs in the queue. index is 0 first.\nusage: <PREFIX>queue 0")]
+#[description("Shows a list of songs in the queue, 10 songs per page. page is 1 first.\nusage: <PREFIX>queue, <PREFIX>queue 3 -> jump to page 3, <PREFIX>queue lofi -> only the songs with `lofi` in the title, <PREFIX>queue 2 lofi")]
 async fn queue(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    let start_queue_index = if let Ok(arg) = args.single::<usize>() {
-        arg
-    } else {
-        0
-    };
+    let page = args.single::<usize>().unwrap_or(1).max(1);
+    let search = parse_search(args.rest());
 
     let guild = if let Some(g) = msg.guild(&ctx.cache) {
         g
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot could'nt get the guild information, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoGuild.into());
     };
     let guild_id = guild.id;
     let manager = if let Some(m) = songbird::get(ctx).await {
         m
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoSongbird.into());
     };
 
     if let Some(_handler_lock) = manager.get(guild_id) {
-        let data = ctx.data.read().await;
-        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
-            let mut sympho_global = sympho_global_mutex.write().await;
-            let sympho_data = sympho_global.entry(guild_id.0).or_insert(SymphoData {
-                volume: 1.0,
-                ..Default::default()
-            });
+        let queue_page = {
+            let data = ctx.data.read().await;
+            if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+                let mut sympho_global = sympho_global_mutex.write().await;
+                let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
 
-            if sympho_data.queue.len() == 0 {
-                check_msg(msg.reply(ctx, "Queue is empty.").await);
+                if sympho_data.queue.len() == 0 {
+                    check_msg(msg.reply(ctx, "Queue is empty.").await);
 
-                return Ok(());
-            }
+                    return Ok(());
+                }
 
-            if start_queue_index >= sympho_data.queue.len() {
+                let pages =
+                    page_count(matching_tracks(&sympho_data.queue, search.as_deref()).len());
+                if page > pages {
+                    return Err(SymphoError::Usage(format!(
+                        "usage: <PREFIX>queue 1\nthe queue has {} pages, page is 1 first.",
+                        pages
+                    ))
+                    .into());
+                }
+
+                queue_page_of(sympho_data, page, search)
+            } else {
                 return Ok(());
             }
+        };
 
-            say_queue_with_embed(msg, ctx, sympho_data, start_queue_index).await;
-        }
-    } else {
         check_msg(
-            msg.reply(ctx, "The bot is not in a voice channel. >_<!")
+            msg.channel_id
+                .send_message(&ctx.http, |m| {
+                    m.embed(|e| queue_embed(e, &queue_page));
+                    m.components(|c| queue_buttons(c, &queue_page));
+                    m
+                })
                 .await,
         );
+    } else {
+        return Err(SymphoError::NotInVoice.into());
     }
 
     Ok(())
 }
 
-async fn say_queue_with_embed(
-    msg: &Message,
-    ctx: &Context,
-    queue: &SymphoData,
-    start_queue_index: usize,
-) {
-    let queue_len = queue.queue.len();
-    let queue_slice = &queue.queue[start_queue_index..{
-        if queue_len - start_queue_index > 10 {
-            start_queue_index + 10
-        } else {
-            queue_len
-        }
-    }];
+// Search words, cut to fit into the custom id of the buttons
+fn parse_search(rest: &str) -> Option<String> {
+    let search = rest
+        .trim()
+        .replace(':', " ")
+        .chars()
+        .take(MAX_SEARCH_LEN)
+        .collect::<String>();
 
-    let titles = queue_slice
+    if search.is_empty() {
+        None
+    } else {
+        Some(search)
+    }
+}
+
+// The songs whose title has `search`(case insensitive) with their number on the queue(1 first)
+pub fn matching_tracks<'a>(
+    queue: &'a [TrackSympho],
+    search: Option<&str>,
+) -> Vec<(usize, &'a TrackSympho)> {
+    let search = search.map(|search| search.to_lowercase());
+
+    queue
         .iter()
         .enumerate()
-        .fold(String::new(), |mut str, (i, q)| {
-            str += &format!("{}: ", start_queue_index + i + 1);
-            str += &format!("[{}]({})\n", &q.title, &q.url);
-            str
-        });
-
-    let durations = queue_slice.iter().fold(String::new(), |mut str, q| {
-        str += &format!("{}\n", format_duration(q.duration));
+        .filter(|(_, track)| {
+            search
+                .as_ref()
+                .map_or(true, |search| track.title.to_lowercase().contains(search))
+        })
+        .map(|(i, track)| (i + 1, track))
+        .collect()
+}
+
+pub fn page_count(len: usize) -> usize {
+    ((len + QUEUE_PAGE_LEN - 1) / QUEUE_PAGE_LEN).max(1)
+}
+
+// Cut `s` to `max` chars at most, the cut is shown with `…`
+pub fn truncate_chars(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        s.chars()
+            .take(max.saturating_sub(1))
+            .chain(std::iter::once('…'))
+            .collect()
+    }
+}
+
+// Title and length fields of the songs, the titles are cut so the field fits into the limit.
+// each line gets an even share of what's left, and the link is dropped if even the url doesn't fit
+pub fn page_fields(entries: &[(usize, &TrackSympho)]) -> (String, String) {
+    let mut titles = String::new();
+
+    for (i, (number, track)) in entries.iter().enumerate() {
+        let budget = (FIELD_LIMIT - titles.chars().count()) / (entries.len() - i);
+        let requester = track
+            .requester
+            .map(|requester| format!(" by {}", requester.mention()))
+            .unwrap_or_default();
+        // `N: [title](url) by @user\n` without the title
+        let frame = format!("{}: []({}){}\n", number, track.url, requester);
+
+        let line = if frame.chars().count() + 1 < budget {
+            format!(
+                "{}: [{}]({}){}\n",
+                number,
+                truncate_chars(
+                    &track.title.replace('[', "(").replace(']', ")"),
+                    budget - frame.chars().count()
+                ),
+                track.url,
+                requester
+            )
+        } else {
+            let frame = format!("{}: {}\n", number, requester);
+            format!(
+                "{}: {}{}\n",
+                number,
+                truncate_chars(
+                    &track.title,
+                    budget.saturating_sub(frame.chars().count()).max(1)
+                ),
+                requester
+            )
+        };
+        titles += &line;
+    }
+
+    let durations = entries.iter().fold(String::new(), |mut str, (_, track)| {
+        str += &format!("{}\n", format_duration(track.duration));
         str
     });
 
-    check_msg(
-        msg.channel_id
-            .send_message(&ctx.http, |m| {
-                m.embed(|e| {
-                    e.author(|a| {
-                        if let Ok(icon) = SYMPHO_ICON
-                            .get_or_init(|| {
-                                Mutex::new(
-                                    "https://cdn.discordapp.com/embed/avatars/0.png".to_string(),
-                                )
-                            })
-                            .lock()
-                        {
-                            a.icon_url(icon);
-                        }
-
-                        if let Ok(name) = SYMPHO_NAME
-                            .get_or_init(|| Mutex::new("Sympho".to_string()))
-                            .lock()
-                        {
-                            a.name(name);
-                        }
-
-                        a.url("https://github.com/2vg/sympho");
-
-                        a
-                    });
-                    e.title("Current queue information");
-                    e.description(format!(
-                        "Queue length: {}\nQueue Duration: {}",
-                        queue.queue.len(),
-                        format_duration(queue.queue_duration)
-                    ));
-                    e.field("Song title", titles, true);
-                    e.field("Song Length", durations, true);
-                    e
-                });
-
-                m
-            })
-            .await,
+    if titles.is_empty() {
+        ("No songs matched.".to_string(), "-".to_string())
+    } else {
+        (titles, durations)
+    }
+}
+
+// One page of the queue, built under the lock so the message is sent without it
+pub struct QueuePage {
+    pub description: String,
+    pub titles: String,
+    pub durations: String,
+    pub page: usize,
+    pub pages: usize,
+    pub search: Option<String>,
+}
+
+pub fn queue_page_of(sympho_data: &SymphoData, page: usize, search: Option<String>) -> QueuePage {
+    let entries = matching_tracks(&sympho_data.queue, search.as_deref());
+    let pages = page_count(entries.len());
+    let page = page.max(1).min(pages);
+    let start = (page - 1) * QUEUE_PAGE_LEN;
+    let end = (start + QUEUE_PAGE_LEN).min(entries.len());
+    let (titles, durations) = page_fields(&entries[start..end]);
+
+    let mut description = format!(
+        "Queue length: {}\nQueue Duration: {}\nLoop: {}\nAutoplay: {}\nPage {} of {}",
+        sympho_data.queue.len(),
+        format_duration(sympho_data.queue_duration),
+        sympho_data.loop_mode,
+        if sympho_data.autoplay { "on" } else { "off" },
+        page,
+        pages
     );
+    if let Some(search) = &search {
+        description += &format!("\nSearch: `{}`({} songs)", search, entries.len());
+    }
+
+    QueuePage {
+        description,
+        titles,
+        durations,
+        page,
+        pages,
+        search,
+    }
+}
+
+fn queue_embed<'a>(e: &'a mut CreateEmbed, queue_page: &QueuePage) -> &'a mut CreateEmbed {
+    e.author(sympho_author);
+    e.title("Current queue information");
+    e.description(&queue_page.description);
+    e.field("Song title", &queue_page.titles, true);
+    e.field("Song Length", &queue_page.durations, true);
+    e
+}
+
+fn queue_buttons<'a>(
+    c: &'a mut CreateComponents,
+    queue_page: &QueuePage,
+) -> &'a mut CreateComponents {
+    let search = queue_page.search.clone().unwrap_or_default();
+    let first = queue_page.page == 1;
+    let last = queue_page.page == queue_page.pages;
+
+    c.create_action_row(|row| {
+        for (direction, label, disabled) in &[
+            ("first", "⏮", first),
+            ("prev", "◀", first),
+            ("next", "▶", last),
+            ("last", "⏭", last),
+        ] {
+            row.create_button(|b| {
+                b.custom_id(format!(
+                    "{}{}:{}:{}",
+                    QUEUE_BUTTON_PREFIX, direction, queue_page.page, search
+                ))
+                .label(label)
+                .style(ButtonStyle::Secondary)
+                .disabled(*disabled)
+            });
+        }
+        row
+    })
+}
+
+// Direction, page and search words of the page button, None if the id is not of a page button
+fn parse_page_button(button: &str) -> Option<(&str, usize, Option<String>)> {
+    let mut parts = button.splitn(3, ':');
+    let direction = parts.next()?;
+    let page = parts.next()?.parse::<usize>().ok()?;
+    let search = parse_search(parts.next().unwrap_or(""));
+
+    match direction {
+        "first" | "prev" | "next" | "last" => Some((direction, page, search)),
+        _ => None,
+    }
+}
+
+// Page the button moves to, the queue may have less pages than when the message was sent
+fn target_page(direction: &str, page: usize, pages: usize) -> usize {
+    let page = match direction {
+        "first" => 1,
+        "prev" => page.saturating_sub(1),
+        "next" => page + 1,
+        _ => pages,
+    };
+
+    page.max(1).min(pages)
+}
+
+// A page button of the queue was pressed, the message is edited to show the page.
+// the user needs the permission of `queue`
+pub async fn press_queue_button(ctx: &Context, interaction: &MessageComponentInteraction) {
+    let button = if let Some(button) = interaction.data.custom_id.strip_prefix(QUEUE_BUTTON_PREFIX)
+    {
+        button
+    } else {
+        return;
+    };
+    let guild_id = if let Some(guild_id) = interaction.guild_id {
+        guild_id
+    } else {
+        return;
+    };
+    let msg = if let InteractionMessage::Regular(msg) = &interaction.message {
+        msg.clone()
+    } else {
+        return;
+    };
+
+    let msg = message_as_user(
+        ctx,
+        msg,
+        &interaction.user,
+        interaction.member.as_ref(),
+        Some(guild_id),
+        "queue",
+        "",
+    )
+    .await;
+    let allowed = before(ctx, &msg, "queue").await;
+
+    let queue_page = if allowed {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let sympho_global = sympho_global_mutex.read().await;
+            match (sympho_global.get(&guild_id.0), parse_page_button(button)) {
+                (Some(sympho_data), Some((direction, page, search))) => {
+                    let pages =
+                        page_count(matching_tracks(&sympho_data.queue, search.as_deref()).len());
+                    let page = target_page(direction, page, pages);
+                    Some(queue_page_of(sympho_data, page, search))
+                }
+                _ => None,
+            }
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let responded = if let Some(queue_page) = queue_page {
+        interaction
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.create_embed(|e| queue_embed(e, &queue_page))
+                            .components(|c| queue_buttons(c, &queue_page))
+                    })
+            })
+            .await
+    } else {
+        interaction
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::DeferredUpdateMessage)
+            })
+            .await
+    };
+
+    if let Err(why) = responded {
+        println!("Couldn't respond to the button: {:?}", why);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(title: &str) -> TrackSympho {
+        TrackSympho {
+            url: format!("https://example.com/{}", title.len()),
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pages_and_search() {
+        let queue = (0..25)
+            .map(|i| track(&format!("song {}", i)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(page_count(0), 1);
+        assert_eq!(page_count(10), 1);
+        assert_eq!(page_count(25), 3);
+
+        // song 1 and song 10 ~ song 19
+        let matched = matching_tracks(&queue, Some("SONG 1"));
+        assert_eq!(
+            matched
+                .iter()
+                .map(|(number, _)| *number)
+                .collect::<Vec<_>>(),
+            std::iter::once(2).chain(11..=20).collect::<Vec<_>>()
+        );
+
+        let queue_page = queue_page_of(
+            &SymphoData {
+                queue,
+                ..Default::default()
+            },
+            5,
+            Some("song 1".to_string()),
+        );
+        assert_eq!((queue_page.page, queue_page.pages), (2, 2));
+        assert_eq!(queue_page.titles.lines().count(), 1);
+    }
+
+    #[test]
+    fn long_titles_fit_into_field() {
+        let long_title = "a".repeat(2000);
+        let queue = (0..QUEUE_PAGE_LEN)
+            .map(|_| track(&long_title))
+            .collect::<Vec<_>>();
+        let entries = matching_tracks(&queue, None);
+
+        let (titles, durations) = page_fields(&entries);
+        assert!(titles.chars().count() <= FIELD_LIMIT);
+        assert_eq!(titles.lines().count(), QUEUE_PAGE_LEN);
+        assert!(titles.contains('…'));
+        assert_eq!(durations.lines().count(), QUEUE_PAGE_LEN);
+    }
+
+    #[test]
+    fn page_buttons() {
+        assert_eq!(parse_page_button("next:1:"), Some(("next", 1, None)));
+        assert_eq!(
+            parse_page_button("last:2:lofi hip hop"),
+            Some(("last", 2, Some("lofi hip hop".to_string())))
+        );
+        assert_eq!(parse_page_button("nope:1:"), None);
+
+        assert_eq!(target_page("next", 1, 3), 2);
+        assert_eq!(target_page("prev", 1, 3), 1);
+        assert_eq!(target_page("last", 1, 3), 3);
+        assert_eq!(target_page("next", 5, 3), 3);
+    }
 }
@@ -0,0 +1,58 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const REMOVE_USAGE: &str = "example usage: <PREFIX>remove 5 -> remove No.5 song from queue.
+example usage: <PREFIX>remove 1 10 -> remove No.1 ~ N0.10 songs from queue.";
+
+#[command]
+#[aliases("rm")]
+#[only_in(guilds)]
+#[description("Remove specified number of songs from the queue.\nusage: <PREFIX>remove 5 or <PREFIX>remove 1 10")]
+async fn remove(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (start, end) = match parse_queue_index(&mut args) {
+        Some(QueueIndex::Single(index)) if index != 0 => (index, index),
+        Some(QueueIndex::Range(start, end)) => (start, end),
+        _ => return Err(SymphoError::Usage(REMOVE_USAGE.to_string()).into()),
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            if let Some(removed) = sympho_data.remove_tracks(start, end) {
+                let reply = if let [track] = removed.as_slice() {
+                    format!("Removed `{}` from queue.", track.title)
+                } else {
+                    format!("Removed {} songs from queue.", removed.len())
+                };
+                check_msg(msg.reply(&ctx.http, reply).await);
+            } else {
+                return Err(SymphoError::Usage(format!(
+                    "the queue has {} songs.\n{}",
+                    sympho_data.queue.len(),
+                    REMOVE_USAGE
+                ))
+                .into());
+            }
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
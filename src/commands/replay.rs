@@ -0,0 +1,39 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+#[command]
+#[only_in(guilds)]
+#[description("Play the music currently playing from the beginning.")]
+async fn replay(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            if let Some((current, _)) = &sympho_data.current {
+                current.seek_time(Duration::default())?;
+                check_msg(msg.reply(&ctx.http, "Replaying the current song.").await);
+            } else {
+                return Err(SymphoError::NotPlaying.into());
+            }
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const REVERSE_USAGE: &str = "example usage: <PREFIX>reverse -> reverse the whole queue.
+example usage: <PREFIX>reverse 1 10 -> reverse No.1 ~ No.10 songs.
+example usage: <PREFIX>reverse @user -> reverse only the songs requested by the user.
+example usage: <PREFIX>reverse @user 1 10 -> both of them.";
+
+#[command]
+#[only_in(guilds)]
+#[description(
+    "Reverse the order of the songs on the queue.\nusage: <PREFIX>reverse [@user] [<start> <end>]"
+)]
+async fn reverse(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let filter = if let Some(filter) = parse_queue_filter(&mut args) {
+        filter
+    } else {
+        return Err(SymphoError::Usage(REVERSE_USAGE.to_string()).into());
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            if let Some(len) = sympho_data.reverse_tracks(&filter) {
+                check_msg(
+                    msg.reply(&ctx.http, format!("Reversed {} songs.", len))
+                        .await,
+                );
+            } else {
+                return Err(SymphoError::Usage(format!(
+                    "the queue has {} songs.\n{}",
+                    sympho_data.queue.len(),
+                    REVERSE_USAGE
+                ))
+                .into());
+            }
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
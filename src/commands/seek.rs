@@ -0,0 +1,95 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const SEEK_USAGE: &str = "example usage: <PREFIX>seek 1:30 -> seek to 1 minute 30 seconds.
+example usage: <PREFIX>seek +30s -> seek forward 30 seconds.
+example usage: <PREFIX>seek -10s -> seek backward 10 seconds.";
+
+// Where to seek, the relative ones are from the current position
+enum SeekTo {
+    Absolute(Duration),
+    Forward(Duration),
+    Backward(Duration),
+}
+
+fn parse_seek_to(arg: &str) -> Option<SeekTo> {
+    if let Some(timestamp) = arg.strip_prefix('+') {
+        parse_timestamp(timestamp).map(SeekTo::Forward)
+    } else if let Some(timestamp) = arg.strip_prefix('-') {
+        parse_timestamp(timestamp).map(SeekTo::Backward)
+    } else {
+        parse_timestamp(arg).map(SeekTo::Absolute)
+    }
+}
+
+#[command]
+#[only_in(guilds)]
+#[description("Seek the music currently playing to specified position.\nusage: <PREFIX>seek 1:30, <PREFIX>seek +30s or <PREFIX>seek -10s")]
+async fn seek(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let seek_to = if let Some(seek_to) = args
+        .single::<String>()
+        .ok()
+        .and_then(|arg| parse_seek_to(&arg))
+    {
+        seek_to
+    } else {
+        return Err(SymphoError::Usage(SEEK_USAGE.to_string()).into());
+    };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            if let Some((track_handle, track_sympho)) = &mut sympho_data.current {
+                let position = track_handle.get_info().await?.position;
+                let position = match seek_to {
+                    SeekTo::Absolute(position) => position,
+                    SeekTo::Forward(dur) => position + dur,
+                    SeekTo::Backward(dur) => position.checked_sub(dur).unwrap_or_default(),
+                };
+
+                let duration = track_handle
+                    .metadata()
+                    .duration
+                    .unwrap_or(track_sympho.duration);
+                if duration > Duration::default() && position >= duration {
+                    return Err(SymphoError::Usage(format!(
+                        "The song is only {} long.\n{}",
+                        dur_to_hhmmss(duration),
+                        SEEK_USAGE
+                    ))
+                    .into());
+                }
+
+                track_handle.seek_time(position)?;
+                // where the song continues from, if it's opened again(e.g. resumed after a restart)
+                track_sympho.start = position;
+                check_msg(
+                    msg.reply(&ctx.http, format!("Seeked to {}.", dur_to_hhmmss(position)))
+                        .await,
+                );
+            } else {
+                return Err(SymphoError::NotPlaying.into());
+            }
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
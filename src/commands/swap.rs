@@ -0,0 +1,55 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+
+const SWAP_USAGE: &str = "example usage: <PREFIX>swap 1 5 -> swap No.1 song and No.5 song.";
+
+#[command]
+#[only_in(guilds)]
+#[description("Swap the two songs on the queue.\nusage: <PREFIX>swap 1 5")]
+async fn swap(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (a, b) =
+        if let (2, Ok(a), Ok(b)) = (args.len(), args.single::<usize>(), args.single::<usize>()) {
+            (a, b)
+        } else {
+            return Err(SymphoError::Usage(SWAP_USAGE.to_string()).into());
+        };
+
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    if let Some(_handler_lock) = manager.get(guild_id) {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+            if sympho_data.swap_tracks(a, b) {
+                check_msg(
+                    msg.reply(&ctx.http, format!("Swapped No.{} and No.{} songs.", a, b))
+                        .await,
+                );
+            } else {
+                return Err(SymphoError::Usage(format!(
+                    "the queue has {} songs.\n{}",
+                    sympho_data.queue.len(),
+                    SWAP_USAGE
+                ))
+                .into());
+            }
+        }
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    }
+
+    Ok(())
+}
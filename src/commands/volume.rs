@@ -1,6 +1,9 @@
 use crate::define::*;
+use crate::error::*;
 use crate::import::*;
 
+const VOLUME_USAGE: &str = "usage: <PREFIX>volume 12.8\nrange is 0.1 ~ 100.0.";
+
 #[command]
 #[aliases("v", "vol")]
 #[only_in(guilds)]
@@ -11,38 +14,24 @@ async fn volume(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
             if vol >= 0.1 && vol <= 100.0 {
                 vol
             } else {
-                return Ok(());
+                return Err(SymphoError::Usage(VOLUME_USAGE.to_string()).into());
             }
         }
         Err(_) => {
-            return Ok(());
+            return Err(SymphoError::Usage(VOLUME_USAGE.to_string()).into());
         }
     };
 
     let guild = if let Some(g) = msg.guild(&ctx.cache) {
         g
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot could'nt get the guild information, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoGuild.into());
     };
     let guild_id = guild.id;
     let manager = if let Some(m) = songbird::get(ctx).await {
         m
     } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return Ok(());
+        return Err(SymphoError::NoSongbird.into());
     };
 
     let volume = volume / 100.0;
@@ -51,10 +40,7 @@ async fn volume(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
         let data = ctx.data.read().await;
         if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
             let mut sympho_global = sympho_global_mutex.write().await;
-            let sympho_data = sympho_global.entry(guild_id.0).or_insert(SymphoData {
-                volume: 1.0,
-                ..Default::default()
-            });
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
 
             sympho_data.volume = volume;
 
@@ -63,10 +49,7 @@ async fn volume(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
             }
         }
     } else {
-        check_msg(
-            msg.reply(ctx, "The bot is not in a voice channel. >_<!")
-                .await,
-        );
+        return Err(SymphoError::NotInVoice.into());
     }
 
     Ok(())
@@ -0,0 +1,95 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+use crate::permission::*;
+
+#[command]
+#[aliases("vs")]
+#[only_in(guilds)]
+#[description("Vote to skip the music currently playing, skipped when enough listeners voted. the dj skips it at once.\nthe share of the listeners needed is set by <PREFIX>config set voteskip_ratio")]
+async fn voteskip(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return Err(SymphoError::NoGuild.into());
+    };
+    let guild_id = guild.id;
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird.into());
+    };
+
+    let roles = msg
+        .member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+    let is_dj = permissions_of(ctx, guild_id.0)
+        .await
+        .level_of_user(&guild, msg.author.id, &roles)
+        >= PermissionLevel::Dj;
+
+    let handler_lock = if let Some(handler_lock) = manager.get(guild_id) {
+        handler_lock
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    };
+    let bot_channel = if let Some(bot_channel) = handler_lock.lock().await.current_channel() {
+        ChannelId(bot_channel.0)
+    } else {
+        return Err(SymphoError::NotInVoice.into());
+    };
+
+    let listeners = listeners_in(&guild, bot_channel);
+    if !listeners.contains(&msg.author.id) {
+        return Err(SymphoError::UserNotInVoice.into());
+    }
+
+    let data = ctx.data.read().await;
+    if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+        let current = if let Some((current, _)) = &sympho_data.current {
+            current.clone()
+        } else {
+            return Err(SymphoError::NotPlaying.into());
+        };
+
+        if is_dj {
+            current.stop()?;
+            check_msg(
+                msg.reply(&ctx.http, "Currently playing song skipped by the dj.")
+                    .await,
+            );
+            return Ok(());
+        }
+
+        sympho_data.skip_votes.insert(msg.author.id);
+        // the users who left the channel don't count anymore
+        let votes = sympho_data.skip_votes.intersection(&listeners).count();
+        let needed = votes_needed(listeners.len(), sympho_data.settings.voteskip_ratio);
+
+        if votes >= needed {
+            current.stop()?;
+            check_msg(
+                msg.reply(
+                    &ctx.http,
+                    format!(
+                        "Vote passed({}/{}), currently playing song skipped.",
+                        votes, needed
+                    ),
+                )
+                .await,
+            );
+        } else {
+            check_msg(
+                msg.reply(&ctx.http, format!("Voted to skip ({}/{}).", votes, needed))
+                    .await,
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,45 +1,514 @@
 use crate::commands::*;
 use crate::import::*;
+use crate::settings::*;
+use crate::slash::*;
+use crate::snapshot::*;
+use crate::watchdog::*;
 
 // Global var
 pub static SYMPHO_ICON: OnceCell<Mutex<String>> = OnceCell::new();
 pub static SYMPHO_NAME: OnceCell<Mutex<String>> = OnceCell::new();
 pub static SYMPHO_PREFIX: OnceCell<Mutex<String>> = OnceCell::new();
 
+// How many played songs are kept for `history` and `previous`
+pub const HISTORY_LEN: usize = 20;
+
+// How many search results autoplay looks through for a song not played recently
+pub const AUTOPLAY_CANDIDATES: usize = 10;
+
+// Default share of the listeners needed to pass a vote skip
+pub const DEFAULT_VOTESKIP_RATIO: f32 = 0.5;
+
+// Upper limit of one youtube-dl lookup
+pub const YTDL_TIMEOUT_SECS: u64 = 30;
+
+// Ticket that identifies a placeholder or a playback start until it completes
+static NEXT_TICKET: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_ticket() -> u64 {
+    NEXT_TICKET.fetch_add(1, Ordering::Relaxed)
+}
+
 // Track Info
-#[derive(Clone, Debug)]
+// `pending` holds the resolution ticket while the entry is still a placeholder
+// `requester` is the user who added the song
+// `start` is the position to start playing from(e.g. `t=` of the url)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TrackSympho {
     pub url: String,
     pub title: String,
     pub thumb: Option<String>,
     pub duration: Duration,
+    // tickets don't mean anything after a restart
+    #[serde(skip)]
+    pub pending: Option<u64>,
+    pub requester: Option<UserId>,
+    pub start: Duration,
+    pub is_live: bool,
+}
+
+// Default cap of the queue length
+pub const DEFAULT_MAX_QUEUE_LEN: usize = 1000;
+
+// What the guild accepts on the queue, None means no limit
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueLimits {
+    pub max_queue_len: Option<usize>,
+    pub max_duration: Option<Duration>,
+    pub max_per_user: Option<usize>,
+    pub allow_live: bool,
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self {
+            max_queue_len: Some(DEFAULT_MAX_QUEUE_LEN),
+            max_duration: None,
+            max_per_user: None,
+            allow_live: true,
+        }
+    }
+}
+
+// Why a song was not added to the queue
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rejection {
+    QueueFull,
+    UserQuota,
+    TooLong,
+    Live,
+}
+
+// Loop mode of the guild, kept across the tracks
+// `Track` repeats the current song, `Queue` puts the finished song back to the end of the queue
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoopMode {
+    Off,
+    Track,
+    Queue,
+}
+
+impl Default for LoopMode {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+impl fmt::Display for LoopMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Track => write!(f, "track"),
+            Self::Queue => write!(f, "queue"),
+        }
+    }
+}
+
+impl std::str::FromStr for LoopMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            // `on` is the old single track loop
+            "track" | "on" => Ok(Self::Track),
+            "queue" => Ok(Self::Queue),
+            _ => Err(()),
+        }
+    }
 }
 
 // Global Queue Struct that used in Sympho
+// `resolving` keeps a cancel sender per placeholder ticket, dropping it aborts the lookup
+// `starting` is the ticket of the playback driver opening the next source, if any
+// `history` is the played songs, the last one is the latest
+// `autoplay` adds a related song when the queue runs dry
+// `fair_share` puts the added songs in the turn of their requester
+// `skip_votes` is the users who voted to skip the current song, cleared when the song changes
+// `text_channel` is where the track events of the voice connection are announced
+// `panel` is the now playing message with the buttons, updated by the track events
 #[derive(Debug, Default)]
 pub struct SymphoData {
     pub current: Option<(TrackHandle, TrackSympho)>,
     pub volume: f32,
     pub queue: Vec<TrackSympho>,
     pub queue_duration: Duration,
+    pub resolving: HashMap<u64, oneshot::Sender<()>>,
+    pub starting: Option<u64>,
+    pub loop_mode: LoopMode,
+    pub history: VecDeque<TrackSympho>,
+    pub autoplay: bool,
+    pub fair_share: bool,
+    pub skip_votes: HashSet<UserId>,
+    pub settings: GuildSettings,
+    pub text_channel: Option<ChannelId>,
+    pub panel: Option<(ChannelId, MessageId)>,
+    // the current song was announced, `Play` is raised again on resume
+    pub announced: bool,
+    pub announcement: Option<(ChannelId, MessageId)>,
+    // timers of the watchdog, a timer leaves only if its ticket is still here when it fires
+    pub idle_ticket: Option<u64>,
+    pub alone_ticket: Option<u64>,
+    // paused by the watchdog, resumed when someone comes back
+    pub paused_alone: bool,
+}
+
+impl SymphoData {
+    // State of a guild that isn't playing anything yet, the volume starts at the configured one
+    pub fn for_guild(settings: GuildSettings) -> Self {
+        Self {
+            volume: settings.volume,
+            settings,
+            ..Default::default()
+        }
+    }
+
+    // Cancel the lookups whose placeholder is no longer in the queue
+    pub fn cancel_orphan_resolutions(&mut self) {
+        let queue = &self.queue;
+        self.resolving
+            .retain(|ticket, _| queue.iter().any(|track| track.pending == Some(*ticket)));
+    }
+
+    // Remove No.`start` ~ No.`end` songs(1 first, inclusive) from the queue.
+    // returns None if the range is not in the queue
+    pub fn remove_tracks(&mut self, start: usize, end: usize) -> Option<Vec<TrackSympho>> {
+        if start < 1 || start > end || end > self.queue.len() {
+            return None;
+        }
+
+        let removed = self.queue.drain(start - 1..end).collect::<Vec<_>>();
+        self.queue_duration -= removed.iter().map(|track| track.duration).sum::<Duration>();
+        // removed placeholders don't need their lookup anymore
+        self.cancel_orphan_resolutions();
+
+        Some(removed)
+    }
+
+    // Move No.`from` song to No.`to`(1 first), the songs between them shift by one
+    pub fn move_track(&mut self, from: usize, to: usize) -> bool {
+        let len = self.queue.len();
+        if from < 1 || to < 1 || from > len || to > len {
+            return false;
+        }
+
+        let track = self.queue.remove(from - 1);
+        self.queue.insert(to - 1, track);
+
+        true
+    }
+
+    // Swap No.`a` song and No.`b` song(1 first)
+    pub fn swap_tracks(&mut self, a: usize, b: usize) -> bool {
+        let len = self.queue.len();
+        if a < 1 || b < 1 || a > len || b > len {
+            return false;
+        }
+
+        self.queue.swap(a - 1, b - 1);
+
+        true
+    }
+
+    // Remember the played song, the oldest one is forgotten over `HISTORY_LEN`
+    pub fn push_history(&mut self, mut track: TrackSympho) {
+        track.start = Duration::default();
+        self.history.push_back(track);
+        while self.history.len() > HISTORY_LEN {
+            self.history.pop_front();
+        }
+    }
+
+    // Split `tracks` into the ones `limits` accepts on the current queue and the rejected ones.
+    // songs without requester(e.g. autoplay) don't count for the user quota
+    pub fn admit_tracks(
+        &self,
+        tracks: Vec<TrackSympho>,
+    ) -> (Vec<TrackSympho>, Vec<(TrackSympho, Rejection)>) {
+        let limits = &self.settings.limits;
+        let mut accepted: Vec<TrackSympho> = vec![];
+        let mut rejected = vec![];
+
+        for track in tracks {
+            let rejection = if track.is_live && !limits.allow_live {
+                Some(Rejection::Live)
+            } else if limits
+                .max_duration
+                .map_or(false, |max| !track.is_live && track.duration > max)
+            {
+                Some(Rejection::TooLong)
+            } else if limits
+                .max_queue_len
+                .map_or(false, |max| self.queue.len() + accepted.len() >= max)
+            {
+                Some(Rejection::QueueFull)
+            } else if track.requester.is_some()
+                && limits.max_per_user.map_or(false, |max| {
+                    let queued = self
+                        .queue
+                        .iter()
+                        .chain(accepted.iter())
+                        .filter(|queued| queued.requester == track.requester)
+                        .count();
+                    queued >= max
+                })
+            {
+                Some(Rejection::UserQuota)
+            } else {
+                None
+            };
+
+            if let Some(rejection) = rejection {
+                rejected.push((track, rejection));
+            } else {
+                accepted.push(track);
+            }
+        }
+
+        (accepted, rejected)
+    }
+
+    // Round-robin the queue by requester, 1st song of each user, then 2nd song of each user...
+    // the order of the songs of one user and the order of the users are kept
+    pub fn fair_share_order(&mut self) {
+        let mut counts = HashMap::new();
+        let mut rounds = self
+            .queue
+            .drain(..)
+            .map(|track| {
+                let count = counts.entry(track.requester).or_insert(0);
+                *count += 1;
+                (*count, track)
+            })
+            .collect::<Vec<_>>();
+
+        // stable, so the order in the same round is kept
+        rounds.sort_by_key(|(round, _)| *round);
+
+        self.queue = rounds.into_iter().map(|(_, track)| track).collect();
+    }
+
+    // Insert `tracks` where `fair_share_order` would put them, after the last song of their requester
+    // and after the other songs of the same round. the songs on the queue are not moved,
+    // so the order made by playnext or move is kept
+    pub fn insert_fair_share(&mut self, tracks: Vec<TrackSympho>) {
+        for track in tracks {
+            let mut counts = HashMap::new();
+            let rounds = self
+                .queue
+                .iter()
+                .map(|queued| {
+                    let count = counts.entry(queued.requester).or_insert(0);
+                    *count += 1;
+                    *count
+                })
+                .collect::<Vec<_>>();
+            let round = counts.get(&track.requester).copied().unwrap_or(0) + 1;
+
+            let after_own = self
+                .queue
+                .iter()
+                .rposition(|queued| queued.requester == track.requester)
+                .map_or(0, |i| i + 1);
+            let index = (after_own..self.queue.len())
+                .find(|i| rounds[*i] > round)
+                .unwrap_or(self.queue.len());
+
+            self.queue.insert(index, track);
+        }
+    }
+
+    // Empty the queue, the current playing song is kept
+    pub fn clear_queue(&mut self) {
+        self.queue = Vec::default();
+        self.queue_duration = Duration::default();
+        self.resolving.clear();
+    }
+
+    // Positions(0 first) of the songs picked by `filter`, None if the range is not in the queue
+    fn filtered_positions(&self, filter: &QueueFilter) -> Option<Vec<usize>> {
+        let (start, end) = filter.range.unwrap_or((1, self.queue.len()));
+        if start < 1 || start > end || end > self.queue.len() {
+            return None;
+        }
+
+        Some(
+            (start - 1..end)
+                .filter(|i| {
+                    filter.requester.is_none() || self.queue[*i].requester == filter.requester
+                })
+                .collect(),
+        )
+    }
+
+    // Rearrange the songs picked by `filter` with `f`, the other songs keep their position.
+    // returns how many songs were picked
+    fn rearrange_tracks<F>(&mut self, filter: &QueueFilter, f: F) -> Option<usize>
+    where
+        F: FnOnce(&mut Vec<TrackSympho>),
+    {
+        let positions = self.filtered_positions(filter)?;
+        let mut picked = positions
+            .iter()
+            .map(|i| std::mem::take(&mut self.queue[*i]))
+            .collect::<Vec<_>>();
+
+        f(&mut picked);
+
+        for (i, track) in positions.iter().zip(picked) {
+            self.queue[*i] = track;
+        }
+
+        Some(positions.len())
+    }
+
+    pub fn shuffle_tracks(&mut self, filter: &QueueFilter) -> Option<usize> {
+        self.rearrange_tracks(filter, |tracks| {
+            let mut rng = rand::thread_rng();
+            tracks.shuffle(&mut rng);
+        })
+    }
+
+    pub fn reverse_tracks(&mut self, filter: &QueueFilter) -> Option<usize> {
+        self.rearrange_tracks(filter, |tracks| tracks.reverse())
+    }
+
+    // Remove the songs picked by `filter` that have the same url as an earlier song in the queue,
+    // returns how many songs were removed
+    pub fn dedupe_tracks(&mut self, filter: &QueueFilter) -> Option<usize> {
+        let positions = self.filtered_positions(filter)?;
+
+        let mut seen = HashSet::new();
+        let mut duplicated = HashSet::new();
+        for (i, track) in self.queue.iter().enumerate() {
+            // placeholders don't have their url resolved yet
+            if track.pending.is_some() {
+                continue;
+            }
+            if !seen.insert(track.url.as_str()) && positions.contains(&i) {
+                duplicated.insert(i);
+            }
+        }
+
+        let mut i = 0;
+        let mut removed_dur = Duration::default();
+        self.queue.retain(|track| {
+            let keep = !duplicated.contains(&i);
+            if !keep {
+                removed_dur += track.duration;
+            }
+            i += 1;
+            keep
+        });
+        self.queue_duration -= removed_dur;
+
+        Some(duplicated.len())
+    }
+}
+
+// Position on the queue given to the commands like skip, remove
+// numbers are 1 first like the queue command shows, `Single(0)` means the current song
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QueueIndex {
+    Single(usize),
+    Range(usize, usize),
+}
+
+// Songs that shuffle, reverse and dedupe work on, the whole queue by default
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueueFilter {
+    pub range: Option<(usize, usize)>,
+    pub requester: Option<UserId>,
+}
+
+// `[@user] [<start> <end>]`, returns None if the arguments are not like that
+pub fn parse_queue_filter(args: &mut Args) -> Option<QueueFilter> {
+    let mut filter = QueueFilter::default();
+
+    // plain numbers are parsed as UserId too, so only take a mention
+    if args.current().map_or(false, |arg| arg.starts_with("<@")) {
+        filter.requester = Some(args.single::<UserId>().ok()?);
+    }
+
+    match args.remaining() {
+        0 => {}
+        2 => {
+            let start = args.single::<usize>().ok()?;
+            let end = args.single::<usize>().ok()?;
+            if start < 1 || start >= end {
+                return None;
+            }
+            filter.range = Some((start, end));
+        }
+        _ => return None,
+    }
+
+    Some(filter)
+}
+
+// `<number>` or `<start> <end>`, returns None if the arguments are not like that
+pub fn parse_queue_index(args: &mut Args) -> Option<QueueIndex> {
+    match args.len() {
+        1 => args.single::<usize>().ok().map(QueueIndex::Single),
+        2 => {
+            let start = args.single::<usize>().ok()?;
+            let end = args.single::<usize>().ok()?;
+            if start < 1 || start >= end {
+                return None;
+            }
+            Some(QueueIndex::Range(start, end))
+        }
+        _ => None,
+    }
 }
 
 // For Serenity's Global data
 // Because it will share across threads, so need RwLock and Arc for sharing reference
 pub struct SymphoGlobal;
 
+pub type SymphoMap = Arc<RwLock<HashMap<u64, SymphoData>>>;
+
+// State of the guild, created with the default settings if the guild has none yet
+pub fn guild_entry(sympho_global: &mut HashMap<u64, SymphoData>, key: u64) -> &mut SymphoData {
+    sympho_global
+        .entry(key)
+        .or_insert_with(|| SymphoData::for_guild(GuildSettings::default()))
+}
+
 impl TypeMapKey for SymphoGlobal {
-    type Value = Arc<RwLock<HashMap<u64, SymphoData>>>;
+    type Value = SymphoMap;
 }
 
 // basic handler struct
-// for now, only handle when bot connect
+// the snapshots are resumed and saved from here, since they need the voice manager
+// the slash commands are registered on connect and run by `handle_interaction`
 pub struct Handler;
 
 #[async_trait]
 impl EventHandler for Handler {
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+        register_slash_commands(&ctx, &ready).await;
+        start_snapshots(ctx).await;
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        handle_interaction(&ctx, interaction).await;
+    }
+
+    async fn voice_state_update(
+        &self,
+        ctx: Context,
+        guild_id: Option<GuildId>,
+        _old: Option<VoiceState>,
+        new: VoiceState,
+    ) {
+        if let Some(guild_id) = guild_id.or(new.guild_id) {
+            check_listeners(&ctx, guild_id).await;
+        }
     }
 }
 
@@ -47,19 +516,30 @@ impl EventHandler for Handler {
 // command derive represents the command that Sympho has
 #[group]
 #[commands(
-    help, join, leave, play, stop, volume, pause, resume, skip, looping, current, queue
+    help, join, leave, play, playnext, stop, volume, pause, resume, skip, looping, current, queue,
+    move_track, swap, remove, clear, shuffle, reverse, dedupe, seek, history, previous, replay,
+    autoplay, fairshare, limits, voteskip, permission, config, playlist
 )]
 pub struct General;
 
-pub const EXCLUDE_HOOK: &[&str] = &[
-    "help",
-    "q",
-    "queue",
-    "current",
-    "np",
-    "nowplaying",
-    "なうぷれ",
-];
+// Author of the embeds the bot sends, the name and icon of the application
+pub fn sympho_author(a: &mut CreateEmbedAuthor) -> &mut CreateEmbedAuthor {
+    if let Ok(icon) = SYMPHO_ICON
+        .get_or_init(|| Mutex::new("https://cdn.discordapp.com/embed/avatars/0.png".to_string()))
+        .lock()
+    {
+        a.icon_url(icon);
+    }
+
+    if let Ok(name) = SYMPHO_NAME
+        .get_or_init(|| Mutex::new("Sympho".to_string()))
+        .lock()
+    {
+        a.name(name);
+    }
+
+    a.url("https://github.com/2vg/sympho")
+}
 
 pub fn check_msg(result: SerenityResult<Message>) {
     if let Err(why) = result {
@@ -76,27 +556,21 @@ pub fn is_file_url(url: &str) -> bool {
     Path::new(url.path()).extension().is_some()
 }
 
-pub async fn get_source(url: String) -> Result<Input, ()> {
+pub async fn get_source(url: String) -> Result<Input, InputError> {
     if is_file_url(&url) {
-        if let Ok(source) = Restartable::ffmpeg(url.clone(), false).await {
-            //let mut source = Input::from(source);
-            //if let Codec::Opus(ref mut opus) = source.kind {
-            //    opus.allow_passthrough = false;
-            //}
-            Ok(source.into())
-        } else {
-            Err(())
-        }
+        let source = Restartable::ffmpeg(url.clone(), false).await?;
+        //let mut source = Input::from(source);
+        //if let Codec::Opus(ref mut opus) = source.kind {
+        //    opus.allow_passthrough = false;
+        //}
+        Ok(source.into())
     } else {
-        if let Ok(source) = Restartable::ytdl(url.clone(), false).await {
-            //let mut source = Input::from(source);
-            //if let Codec::Opus(ref mut opus) = source.kind {
-            //    opus.allow_passthrough = false;
-            //}
-            Ok(source.into())
-        } else {
-            Err(())
-        }
+        let source = Restartable::ytdl(url.clone(), false).await?;
+        //let mut source = Input::from(source);
+        //if let Codec::Opus(ref mut opus) = source.kind {
+        //    opus.allow_passthrough = false;
+        //}
+        Ok(source.into())
     }
 }
 
@@ -110,18 +584,6 @@ pub fn play_from_source(handler: &mut Call, src: Input, volume: f32) -> TrackHan
     track_handle
 }
 
-pub fn has_dj_user(guild: &Guild, roles: &[RoleId]) -> bool {
-    for role_id in roles {
-        if let Some(role) = guild.roles.get(&role_id) {
-            if role.name == "DJUser" {
-                return true;
-            }
-        }
-    }
-
-    false
-}
-
 pub fn in_channel(guild: &Guild, msg: &Message) -> bool {
     let channel_id = guild
         .voice_states
@@ -134,23 +596,25 @@ pub fn in_channel(guild: &Guild, msg: &Message) -> bool {
     }
 }
 
-pub async fn check_user_can_use_command(guild: &Guild, ctx: &Context, msg: &Message) -> bool {
-    if !in_channel(guild, msg) {
-        check_msg(msg.reply(ctx, "You are not in a voice channel. >_<!").await);
-        return false;
-    }
-    if let Some(member) = &msg.member.as_ref() {
-        if !has_dj_user(guild, &member.roles) {
-            check_msg(
-                msg.reply(ctx, "You don't have the role of `DJUser`. >_<!")
-                    .await,
-            );
-            return false;
-        }
-    } else {
-        return false;
-    }
-    true
+// Users in `channel_id` except the bots
+pub fn listeners_in(guild: &Guild, channel_id: ChannelId) -> HashSet<UserId> {
+    guild
+        .voice_states
+        .iter()
+        .filter(|(_, voice_state)| voice_state.channel_id == Some(channel_id))
+        .filter(|(user_id, _)| {
+            !guild
+                .members
+                .get(user_id)
+                .map_or(false, |member| member.user.bot)
+        })
+        .map(|(user_id, _)| *user_id)
+        .collect()
+}
+
+// How many votes `listeners` people need with `ratio`, at least one
+pub fn votes_needed(listeners: usize, ratio: f32) -> usize {
+    ((listeners as f32 * ratio).ceil() as usize).max(1)
 }
 
 // if bot playing music on other channel, return true.
@@ -176,10 +640,7 @@ pub async fn check_bot_using_at_other_chan(
                 let data = ctx.data.read().await;
                 if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
                     let mut sympho_global = sympho_global_mutex.write().await;
-                    let sympho_data = sympho_global.entry(guild.id.0).or_insert(SymphoData {
-                        volume: 1.0,
-                        ..Default::default()
-                    });
+                    let sympho_data = guild_entry(&mut sympho_global, guild.id.0);
 
                     if let Some((track_handle, _track_sympho)) = &sympho_data.current {
                         if let Ok(info) = track_handle.get_info().await {
@@ -205,6 +666,107 @@ pub fn dur_to_hhmmss(dur: Duration) -> String {
     format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
 }
 
+// Reply for the songs `admit_tracks` rejected, one line per reason
+pub fn describe_rejections(rejected: &[(TrackSympho, Rejection)], limits: &QueueLimits) -> String {
+    let reasons = [
+        Rejection::QueueFull,
+        Rejection::UserQuota,
+        Rejection::TooLong,
+        Rejection::Live,
+    ];
+
+    reasons
+        .iter()
+        .filter_map(|reason| {
+            let tracks = rejected
+                .iter()
+                .filter(|(_, rejection)| rejection == reason)
+                .map(|(track, _)| track)
+                .collect::<Vec<_>>();
+            let songs = match tracks.as_slice() {
+                [] => return None,
+                [track] => format!("`{}`", track.title),
+                tracks => format!("{} songs", tracks.len()),
+            };
+
+            Some(match reason {
+                Rejection::QueueFull => format!(
+                    "{}: the queue is full(up to {} songs).",
+                    songs,
+                    limits.max_queue_len.unwrap_or_default()
+                ),
+                Rejection::UserQuota => format!(
+                    "{}: you already have {} songs on the queue.",
+                    songs,
+                    limits.max_per_user.unwrap_or_default()
+                ),
+                Rejection::TooLong => format!(
+                    "{}: longer than {}.",
+                    songs,
+                    dur_to_hhmmss(limits.max_duration.unwrap_or_default())
+                ),
+                Rejection::Live => format!("{}: live streams are not allowed.", songs),
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// `hh:mm:ss`, `mm:ss`, `90` or youtube style `1h2m3s`, `90s`
+pub fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    if timestamp.is_empty() {
+        return None;
+    }
+
+    if timestamp.contains(':') {
+        let parts = timestamp.split(':').collect::<Vec<_>>();
+        if parts.len() > 3 {
+            return None;
+        }
+        let mut secs = 0;
+        for part in parts {
+            secs = secs * 60 + part.parse::<u64>().ok()?;
+        }
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut secs = 0;
+    let mut number = String::new();
+    for c in timestamp.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        secs += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        secs += number.parse::<u64>().ok()?;
+    }
+
+    Some(Duration::from_secs(secs))
+}
+
+// Start position linked by `t=` or `start=` of the url(query or fragment), e.g. `youtu.be/xxx?t=90`
+pub fn parse_url_timestamp(url: &str) -> Option<Duration> {
+    let url = Url::parse(url).ok()?;
+    let fragment = url
+        .fragment()
+        .map(|fragment| Url::parse(&format!("http://localhost/?{}", fragment)).ok())
+        .flatten();
+
+    url.query_pairs()
+        .chain(fragment.iter().flat_map(|fragment| fragment.query_pairs()))
+        .find(|(key, _)| key == "t" || key == "start")
+        .and_then(|(_, value)| parse_timestamp(&value))
+}
+
 pub fn run_cmd(cmd: &str, args: &[&str], timeout: Option<Duration>) -> Result<Value> {
     let mut child = Command::new(cmd)
         .stdout(Stdio::piped())
@@ -242,6 +804,75 @@ pub fn run_cmd(cmd: &str, args: &[&str], timeout: Option<Duration>) -> Result<Va
     }
 }
 
+// youtube-dl builder that uses the backend chosen by `SYMPHO_YTDL_BACKEND`(pyo3 or subprocess)
+// and the executable set by `SYMPHO_YTDL_PATH`(e.g. yt-dlp) for the subprocess backend
+pub fn new_ytdl(url: &str) -> YoutubeDl {
+    let mut ytdl = YoutubeDl::new(url);
+
+    if let Ok(backend) = env::var("SYMPHO_YTDL_BACKEND") {
+        match backend.parse::<BackendKind>() {
+            Ok(kind) => {
+                ytdl.backend_kind(kind);
+            }
+            Err(why) => println!("{}, using the default backend", why),
+        }
+    }
+
+    if let Ok(path) = env::var("SYMPHO_YTDL_PATH") {
+        ytdl.youtube_dl_path(path);
+    }
+
+    ytdl
+}
+
+// Reason shown to users when youtube-dl could not get the tracks
+pub fn describe_ytdl_error(why: &YtdlError) -> String {
+    match why {
+        YtdlError::UnsupportedUrl(_) => "This URL is not supported. >_<!".to_string(),
+        YtdlError::Unavailable(info) => {
+            format!("This video is unavailable. >_<!\n`{}`", info.message)
+        }
+        YtdlError::AgeRestricted(_) => {
+            "This video is age restricted, the bot can't play it. >_<!".to_string()
+        }
+        YtdlError::Network(_) => {
+            "Couldn't reach the site, please try again later. >_<!".to_string()
+        }
+        YtdlError::NotInstalled(_) => {
+            "youtube-dl is not installed on the bot, please contact to developer. >_<!".to_string()
+        }
+        YtdlError::ProcessTimeout => {
+            "Getting the video information took too long. >_<!".to_string()
+        }
+        YtdlError::Extractor(info) => format!(
+            "youtube-dl failed to get the video information. >_<!\n`{}`",
+            info
+        ),
+        _ => format!(
+            "The bot couldn't get the video information, please contact to developer. >_<!\n`{}`",
+            why
+        ),
+    }
+}
+
+// Reason shown to users when the audio of a song couldn't be opened, the details go to the log
+pub fn describe_input_error(why: &InputError) -> String {
+    match why {
+        InputError::Io(why) if why.kind() == std::io::ErrorKind::NotFound => {
+            "ffmpeg or youtube-dl is not installed on the bot, please contact to developer. >_<!"
+                .to_string()
+        }
+        InputError::YouTubeDlRun(_)
+        | InputError::YouTubeDlProcessing(_)
+        | InputError::YouTubeDlUrl(_)
+        | InputError::Json { .. }
+        | InputError::Metadata => {
+            "youtube-dl couldn't get the audio, the video may be unavailable now.".to_string()
+        }
+        _ => "The audio couldn't be opened.".to_string(),
+    }
+}
+
 pub fn get_audio_file_info(url: &str) -> Result<(String, Duration)> {
     let info = run_cmd(
         "ffprobe",
@@ -270,3 +901,244 @@ pub fn get_audio_file_info(url: &str) -> Result<(String, Duration)> {
     );
     Ok((title, dur))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_of(secs: &[u64]) -> SymphoData {
+        let mut sympho_data = SymphoData::default();
+        for (i, secs) in secs.iter().enumerate() {
+            sympho_data.queue.push(TrackSympho {
+                url: i.to_string(),
+                duration: Duration::from_secs(*secs),
+                ..Default::default()
+            });
+            sympho_data.queue_duration += Duration::from_secs(*secs);
+        }
+        sympho_data
+    }
+
+    fn urls(sympho_data: &SymphoData) -> Vec<&str> {
+        sympho_data
+            .queue
+            .iter()
+            .map(|track| track.url.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(parse_timestamp("1:02:03"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_timestamp("02:03"), Some(Duration::from_secs(123)));
+        assert_eq!(parse_timestamp("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_timestamp("1h2m3s"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_timestamp("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_timestamp("1:2:3:4"), None);
+        assert_eq!(parse_timestamp("abc"), None);
+
+        assert_eq!(
+            parse_url_timestamp("https://www.youtube.com/watch?v=xxx&t=1m30s"),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_url_timestamp("https://youtu.be/xxx?start=42"),
+            Some(Duration::from_secs(42))
+        );
+        assert_eq!(
+            parse_url_timestamp("https://www.youtube.com/watch?v=xxx#t=15"),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            parse_url_timestamp("https://www.youtube.com/watch?v=xxx"),
+            None
+        );
+    }
+
+    #[test]
+    fn remove_tracks_keeps_duration() {
+        let mut sympho_data = queue_of(&[1, 2, 3, 4]);
+
+        assert!(sympho_data.remove_tracks(0, 1).is_none());
+        assert!(sympho_data.remove_tracks(3, 5).is_none());
+        assert_eq!(sympho_data.remove_tracks(2, 3).map(|r| r.len()), Some(2));
+        assert_eq!(urls(&sympho_data), vec!["0", "3"]);
+        assert_eq!(sympho_data.queue_duration, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn move_and_swap_tracks() {
+        let mut sympho_data = queue_of(&[1, 2, 3, 4]);
+
+        assert!(sympho_data.move_track(4, 1));
+        assert_eq!(urls(&sympho_data), vec!["3", "0", "1", "2"]);
+        assert!(sympho_data.swap_tracks(2, 4));
+        assert_eq!(urls(&sympho_data), vec!["3", "2", "1", "0"]);
+        assert!(!sympho_data.move_track(0, 1));
+        assert!(!sympho_data.swap_tracks(1, 5));
+        assert_eq!(sympho_data.queue_duration, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn rearrange_only_filtered_tracks() {
+        let mut sympho_data = queue_of(&[1, 2, 3, 4, 5]);
+        for (i, track) in sympho_data.queue.iter_mut().enumerate() {
+            track.requester = Some(UserId(if i % 2 == 0 { 1 } else { 2 }));
+        }
+
+        let by_user = QueueFilter {
+            requester: Some(UserId(1)),
+            ..Default::default()
+        };
+        assert_eq!(sympho_data.reverse_tracks(&by_user), Some(3));
+        assert_eq!(urls(&sympho_data), vec!["4", "1", "2", "3", "0"]);
+
+        let in_range = QueueFilter {
+            range: Some((2, 4)),
+            ..Default::default()
+        };
+        assert_eq!(sympho_data.reverse_tracks(&in_range), Some(3));
+        assert_eq!(urls(&sympho_data), vec!["4", "3", "2", "1", "0"]);
+
+        let out_of_range = QueueFilter {
+            range: Some((4, 6)),
+            ..Default::default()
+        };
+        assert_eq!(sympho_data.shuffle_tracks(&out_of_range), None);
+        assert_eq!(sympho_data.shuffle_tracks(&QueueFilter::default()), Some(5));
+        assert_eq!(sympho_data.queue_duration, Duration::from_secs(15));
+    }
+
+    #[test]
+    fn dedupe_keeps_first_song() {
+        let mut sympho_data = queue_of(&[1, 2, 3, 4]);
+        sympho_data.queue[2].url = "0".to_string();
+        sympho_data.queue[3].url = "1".to_string();
+
+        let in_range = QueueFilter {
+            range: Some((1, 3)),
+            ..Default::default()
+        };
+        assert_eq!(sympho_data.dedupe_tracks(&in_range), Some(1));
+        assert_eq!(urls(&sympho_data), vec!["0", "1", "1"]);
+        assert_eq!(sympho_data.queue_duration, Duration::from_secs(7));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut sympho_data = SymphoData::default();
+        for i in 0..HISTORY_LEN + 5 {
+            sympho_data.push_history(TrackSympho {
+                url: i.to_string(),
+                start: Duration::from_secs(30),
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(sympho_data.history.len(), HISTORY_LEN);
+        assert_eq!(sympho_data.history.front().unwrap().url, "5");
+        assert_eq!(
+            sympho_data.history.back().unwrap().url,
+            (HISTORY_LEN + 4).to_string()
+        );
+        assert!(sympho_data
+            .history
+            .iter()
+            .all(|track| track.start == Duration::default()));
+    }
+
+    #[test]
+    fn fair_share_interleaves_requesters() {
+        let mut sympho_data = queue_of(&[1, 1, 1, 1, 1, 1]);
+        for (track, user) in sympho_data.queue.iter_mut().zip(&[1, 1, 1, 1, 2, 3]) {
+            track.requester = Some(UserId(*user));
+        }
+
+        sympho_data.fair_share_order();
+        assert_eq!(urls(&sympho_data), vec!["0", "4", "5", "1", "2", "3"]);
+        assert_eq!(sympho_data.queue_duration, Duration::from_secs(6));
+    }
+
+    #[test]
+    fn fair_share_inserts_without_moving_the_queue() {
+        let mut sympho_data = queue_of(&[1, 1, 1, 1]);
+        // user 2 moved their song to the front
+        for (track, user) in sympho_data.queue.iter_mut().zip(&[2, 1, 1, 1]) {
+            track.requester = Some(UserId(*user));
+        }
+
+        let track = |url: &str, user: u64| TrackSympho {
+            url: url.to_string(),
+            requester: Some(UserId(user)),
+            ..Default::default()
+        };
+        sympho_data.insert_fair_share(vec![track("a", 3), track("b", 3)]);
+        assert_eq!(urls(&sympho_data), vec!["0", "1", "a", "2", "b", "3"]);
+
+        sympho_data.insert_fair_share(vec![track("c", 2)]);
+        assert_eq!(urls(&sympho_data), vec!["0", "1", "a", "2", "b", "c", "3"]);
+    }
+
+    #[test]
+    fn admit_tracks_by_limits() {
+        let mut sympho_data = queue_of(&[1, 1]);
+        for track in sympho_data.queue.iter_mut() {
+            track.requester = Some(UserId(1));
+        }
+        sympho_data.settings.limits = QueueLimits {
+            max_queue_len: Some(5),
+            max_duration: Some(Duration::from_secs(60)),
+            max_per_user: Some(3),
+            allow_live: false,
+        };
+
+        let track = |secs: u64, user: u64, is_live: bool| TrackSympho {
+            duration: Duration::from_secs(secs),
+            requester: Some(UserId(user)),
+            is_live,
+            ..Default::default()
+        };
+        let (accepted, rejected) = sympho_data.admit_tracks(vec![
+            track(10, 1, false),
+            track(10, 1, false),
+            track(90, 2, false),
+            track(0, 2, true),
+            track(10, 2, false),
+            track(10, 2, false),
+            track(10, 2, false),
+        ]);
+
+        assert_eq!(accepted.len(), 3);
+        assert_eq!(
+            rejected.iter().map(|(_, why)| *why).collect::<Vec<_>>(),
+            vec![
+                Rejection::UserQuota,
+                Rejection::TooLong,
+                Rejection::Live,
+                Rejection::QueueFull
+            ]
+        );
+    }
+
+    #[test]
+    fn votes_needed_rounds_up() {
+        assert_eq!(votes_needed(0, 0.5), 1);
+        assert_eq!(votes_needed(1, 0.5), 1);
+        assert_eq!(votes_needed(3, 0.5), 2);
+        assert_eq!(votes_needed(4, 0.5), 2);
+        assert_eq!(votes_needed(4, 1.0), 4);
+    }
+
+    #[test]
+    fn guilds_start_with_their_settings() {
+        let mut sympho_global = HashMap::new();
+        assert_eq!(guild_entry(&mut sympho_global, 1).volume, DEFAULT_VOLUME);
+
+        let sympho_data = SymphoData::for_guild(GuildSettings {
+            volume: 0.3,
+            ..Default::default()
+        });
+        assert_eq!(sympho_data.volume, 0.3);
+        assert_eq!(sympho_data.settings.volume, 0.3);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::define::*;
+use crate::import::*;
+use crate::permission::*;
+
+// Error returned by Sympho's commands
+// `after` hook in main.rs turns it into the error embed, so commands just return it
+#[derive(Debug)]
+pub enum SymphoError {
+    // wrong or missing arguments, holds the usage of the command
+    Usage(String),
+    NoGuild,
+    NoSongbird,
+    NotInVoice,
+    UserNotInVoice,
+    JoinFailed,
+    MissingPermission(PermissionLevel),
+    PlayingAtOther(ChannelId),
+    // the guild accepts the commands only in this channel
+    WrongChannel(ChannelId),
+    NoSongs,
+    NotPlaying,
+    Extract(YtdlError),
+    // the sources of these tracks couldn't be opened, holds the list of them
+    Source(String),
+    // some songs were over the queue limits, holds the reasons
+    Rejected(String),
+    // no saved playlist has this name
+    NoPlaylist(String),
+    // the saved playlist is owned by someone else
+    NotPlaylistOwner(String),
+}
+
+impl fmt::Display for SymphoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(usage) => write!(f, "{}", usage),
+            Self::NoGuild => write!(
+                f,
+                "The bot could'nt get the guild information, please contact to developer. >_<!"
+            ),
+            Self::NoSongbird => write!(
+                f,
+                "The bot have something problem, please contact to developer. >_<!"
+            ),
+            Self::NotInVoice => write!(f, "The bot is not in a voice channel. >_<!"),
+            Self::UserNotInVoice => write!(f, "You are not in a voice channel. >_<!"),
+            Self::JoinFailed => write!(f, "Error joining the channel."),
+            Self::MissingPermission(level) => write!(
+                f,
+                "This command needs the `{}` permission, you don't have it. >_<!",
+                level
+            ),
+            Self::PlayingAtOther(channel_id) => write!(
+                f,
+                "The bot is currently playing at {}",
+                channel_id.mention()
+            ),
+            Self::WrongChannel(channel_id) => {
+                write!(f, "Please use the commands in {}.", channel_id.mention())
+            }
+            Self::NoSongs => write!(f, "No songs found. >_<!"),
+            Self::NotPlaying => write!(f, "No songs."),
+            Self::Extract(why) => write!(f, "{}", describe_ytdl_error(why)),
+            Self::Rejected(reasons) => write!(f, "{}", reasons),
+            Self::Source(tracks) => {
+                write!(f, "Couldn't play these songs, skipped. >_<!\n{}", tracks)
+            }
+            Self::NoPlaylist(name) => write!(f, "No playlist named `{}`. >_<!", name),
+            Self::NotPlaylistOwner(name) => write!(
+                f,
+                "`{}` is someone else's playlist, only the owner can edit it. >_<!",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SymphoError {}
+
+impl SymphoError {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Usage(_) => "Invalid arguments",
+            Self::UserNotInVoice
+            | Self::MissingPermission(_)
+            | Self::PlayingAtOther(_)
+            | Self::WrongChannel(_)
+            | Self::NotPlaylistOwner(_) => "You can't use this command",
+            Self::NoSongs | Self::Extract(_) => "Couldn't add the songs",
+            Self::Source(_) => "Couldn't play the songs",
+            Self::Rejected(_) => "Some songs were not added",
+            _ => "Command failed",
+        }
+    }
+}
+
+pub async fn say_error_with_embed(
+    http: &Arc<Http>,
+    channel_id: ChannelId,
+    title: &str,
+    description: &str,
+) {
+    check_msg(
+        channel_id
+            .send_message(http, |m| {
+                m.embed(|e| {
+                    e.author(sympho_author);
+                    e.colour(0xe74c3c);
+                    e.title(title);
+                    e.description(description);
+                    e
+                });
+
+                m
+            })
+            .await,
+    );
+}
+
+pub async fn say_sympho_error(http: &Arc<Http>, channel_id: ChannelId, why: &SymphoError) {
+    say_error_with_embed(http, channel_id, why.title(), &why.to_string()).await;
+}
@@ -1,30 +1,51 @@
+use crate::announce::*;
 use crate::define::*;
 use crate::import::*;
+use crate::panel::*;
+use crate::player::*;
+use crate::resolver::*;
+use crate::watchdog::*;
 
 // Track Start Event
 pub struct TrackStartNotifier {
     pub data: Arc<serenity::prelude::RwLock<TypeMap>>,
     pub key: u64,
-    // chan_id: ChannelId,
-    // http: Arc<Http>,
+    pub chan_id: ChannelId,
+    pub http: Arc<Http>,
 }
 
 // When Track pause and volume change until track pausing
 // Track's volume will not change when Track will resume
 // So, We defined Track Start Event then re-Set the volume
+// The now playing panel shows the new song too, and the song is announced to the text channel
 #[async_trait]
 impl VoiceEventHandler for TrackStartNotifier {
     async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
         if let EventContext::Track(&[(_state, track)]) = ctx {
-            let data = self.data.read().await;
-            if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let sympho_global_mutex = {
+                let data = self.data.read().await;
+                if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+                    sympho_global_mutex.clone()
+                } else {
+                    return None;
+                }
+            };
+
+            {
                 let mut sympho_global = sympho_global_mutex.write().await;
-                let sympho_data = sympho_global.entry(self.key).or_insert(SymphoData {
-                    volume: 1.0,
-                    ..Default::default()
-                });
+                let sympho_data = guild_entry(&mut sympho_global, self.key);
                 let _ = track.set_volume(sympho_data.volume);
             }
+
+            refresh_panel(&self.http, &sympho_global_mutex, self.key).await;
+            announce_track(
+                &self.http,
+                &sympho_global_mutex,
+                self.key,
+                self.chan_id,
+                track,
+            )
+            .await;
         }
 
         None
@@ -36,47 +57,117 @@ pub struct TrackEndNotifier {
     pub handler: Arc<serenity::prelude::Mutex<Call>>,
     pub data: Arc<serenity::prelude::RwLock<TypeMap>>,
     pub key: u64,
-    // chan_id: ChannelId,
-    // http: Arc<Http>,
+    pub chan_id: ChannelId,
+    pub http: Arc<Http>,
 }
 
 // We have to go next track when Track playing ended
 // Sympho will check the ownself queue when raised Track End Event
+// Unplayable tracks are dropped and announced, so the queue never stalls on them
+// With autoplay, a related song is looked up when the queue runs dry
+// `previous` and `stop` take the current song out before stopping it, so an end event
+// for a song that is no longer the current one is late and must not touch the new one
 #[async_trait]
 impl VoiceEventHandler for TrackEndNotifier {
-    async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
-        let mut handler = self.handler.lock().await;
-        let data = self.data.read().await;
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        let ended = if let EventContext::Track(tracks) = ctx {
+            tracks
+                .iter()
+                .map(|(_state, track)| track.uuid())
+                .collect::<Vec<_>>()
+        } else {
+            return None;
+        };
+
+        let sympho_global_mutex = {
+            let data = self.data.read().await;
+            if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+                sympho_global_mutex.clone()
+            } else {
+                return None;
+            }
+        };
 
-        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+        let ended_current = {
             let mut sympho_global = sympho_global_mutex.write().await;
-            let sympho_data = sympho_global.entry(self.key).or_insert(SymphoData {
-                volume: 1.0,
-                ..Default::default()
-            });
+            if let Some(sympho_data) = sympho_global.get_mut(&self.key) {
+                let is_current = sympho_data
+                    .current
+                    .as_ref()
+                    .map_or(false, |(current, _)| ended.contains(&current.uuid()));
 
-            if sympho_data.queue.len() != 0 {
-                let track_sympho = sympho_data.queue[0].clone();
-                let url = track_sympho.url.clone();
+                if let Some((_, mut track)) = sympho_data.current.take().filter(|_| is_current) {
+                    sympho_data.skip_votes.clear();
+                    sympho_data.push_history(track.clone());
 
-                sympho_data.queue.remove(0);
+                    // queue loop plays the finished song again after the others
+                    if sympho_data.loop_mode == LoopMode::Queue {
+                        track.start = Duration::default();
+                        sympho_data.queue_duration += track.duration;
+                        sympho_data.queue.push(track.clone());
+                    }
 
-                let source = if let Ok(source) = get_source(url).await {
-                    source
+                    if sympho_data.autoplay
+                        && sympho_data.queue.is_empty()
+                        && sympho_data.resolving.is_empty()
+                    {
+                        let recent = sympho_data
+                            .history
+                            .iter()
+                            .map(|track| track.url.clone())
+                            .collect::<Vec<_>>();
+                        Some(Some((track, recent)))
+                    } else {
+                        Some(None)
+                    }
                 } else {
-                    return None;
-                };
-
-                sympho_data.queue_duration -= track_sympho.duration;
-
-                sympho_data.current = Some((
-                    play_from_source(&mut handler, source, sympho_data.volume),
-                    track_sympho,
-                ));
+                    None
+                }
             } else {
-                sympho_data.current = None;
-                return None;
+                None
             }
+        };
+
+        let autoplay_seed = if let Some(autoplay_seed) = ended_current {
+            autoplay_seed
+        } else {
+            // nothing to start, but `stop` may have left the guild idle
+            watch_idle(&self.data, &self.http, self.key).await;
+            return None;
+        };
+
+        let dropped = start_next(&self.handler, &sympho_global_mutex, self.key).await;
+        say_dropped_tracks(&self.http, self.chan_id, &dropped).await;
+        watch_idle(&self.data, &self.http, self.key).await;
+        // shows the next song, or that nothing is playing
+        refresh_panel(&self.http, &sympho_global_mutex, self.key).await;
+
+        if let Some((seed, recent)) = autoplay_seed {
+            let resolution = spawn_resolution(
+                sympho_global_mutex.clone(),
+                self.key,
+                seed.url.clone(),
+                None,
+                false,
+                lookup_related(seed, recent),
+            )
+            .await;
+
+            // don't hold the event handler while youtube-dl runs
+            let handler = self.handler.clone();
+            let key = self.key;
+            let chan_id = self.chan_id;
+            let http = self.http.clone();
+            let data = self.data.clone();
+            tokio::spawn(async move {
+                if let Ok(Err(why)) = resolution.await {
+                    println!("Autoplay couldn't find the next song: {:?}", why);
+                }
+
+                let dropped = start_next(&handler, &sympho_global_mutex, key).await;
+                say_dropped_tracks(&http, chan_id, &dropped).await;
+                watch_idle(&data, &http, key).await;
+            });
         }
 
         None
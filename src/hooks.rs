@@ -0,0 +1,194 @@
+use crate::define::*;
+use crate::error::*;
+use crate::import::*;
+use crate::permission::*;
+use crate::settings::*;
+
+// Check user can use command
+// the level of the command comes from the permission settings of the guild
+#[hook]
+pub async fn before(ctx: &Context, msg: &Message, command_name: &str) -> bool {
+    let guild = if let Some(g) = msg.guild(&ctx.cache) {
+        g
+    } else {
+        return false;
+    };
+
+    let permissions = permissions_of(ctx, guild.id.0).await;
+    let action = action_of(&msg.content, &prefix_of(ctx, Some(guild.id.0)).await);
+    let required = permissions.level_of_action(command_name, action.as_deref());
+
+    // admin commands work everywhere, so the binding can be changed
+    if required != PermissionLevel::Admin {
+        if let Some(bound_channel) = bound_channel_of(ctx, guild.id.0).await {
+            if bound_channel != msg.channel_id {
+                say_sympho_error(
+                    &ctx.http,
+                    msg.channel_id,
+                    &SymphoError::WrongChannel(bound_channel),
+                )
+                .await;
+                return false;
+            }
+        }
+    }
+
+    if required == PermissionLevel::Everyone {
+        return true;
+    }
+
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        say_sympho_error(&ctx.http, msg.channel_id, &SymphoError::NoSongbird).await;
+        return false;
+    };
+
+    let bot_channel = if let Some(handler_lock) = manager.get(guild.id) {
+        handler_lock
+            .lock()
+            .await
+            .current_channel()
+            .map(|bot_channel| ChannelId(bot_channel.0))
+    } else {
+        None
+    };
+
+    let roles = msg
+        .member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+    let level = permissions.level_of_user(&guild, msg.author.id, &roles);
+    let alone = required == PermissionLevel::Dj
+        && permissions.allow_when_alone
+        && is_alone_with_bot(&guild, msg.author.id, bot_channel);
+
+    if level < required && !alone {
+        say_sympho_error(
+            &ctx.http,
+            msg.channel_id,
+            &SymphoError::MissingPermission(required),
+        )
+        .await;
+        return false;
+    }
+
+    // admin commands only change the settings, no need to be in the voice channel
+    if required == PermissionLevel::Admin {
+        return true;
+    }
+
+    if !in_channel(&guild, msg) {
+        say_sympho_error(&ctx.http, msg.channel_id, &SymphoError::UserNotInVoice).await;
+        return false;
+    }
+
+    if check_bot_using_at_other_chan(&manager, &guild, msg, ctx).await {
+        if let Some(bot_channel) = bot_channel {
+            say_sympho_error(
+                &ctx.http,
+                msg.channel_id,
+                &SymphoError::PlayingAtOther(bot_channel),
+            )
+            .await;
+        }
+        return false;
+    }
+
+    true
+}
+
+// Prefix of the guild the message came from
+#[hook]
+pub async fn dynamic_prefix(ctx: &Context, msg: &Message) -> Option<String> {
+    Some(prefix_of(ctx, msg.guild_id.map(|guild_id| guild_id.0)).await)
+}
+
+// Show the error embed when the command returned an error
+#[hook]
+pub async fn after(
+    ctx: &Context,
+    msg: &Message,
+    command_name: &str,
+    command_result: CommandResult,
+) {
+    if let Err(why) = command_result {
+        if let Some(sympho_error) = why.downcast_ref::<SymphoError>() {
+            say_sympho_error(&ctx.http, msg.channel_id, sympho_error).await;
+        } else {
+            println!("Command '{}' returned error {:?}", command_name, why);
+            say_error_with_embed(
+                &ctx.http,
+                msg.channel_id,
+                "Command failed",
+                "The bot have something problem, please contact to developer. >_<!",
+            )
+            .await;
+        }
+    }
+}
+
+// Show the error embed when the command couldn't be dispatched(e.g. wrong number of arguments)
+#[hook]
+pub async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError) {
+    let description = match error {
+        DispatchError::NotEnoughArguments { min, given } => {
+            format!("Need {} arguments, but only {} given.", min, given)
+        }
+        DispatchError::TooManyArguments { max, given } => {
+            format!("Max {} arguments allowed, but {} given.", max, given)
+        }
+        DispatchError::OnlyForGuilds => "This command can only be used in a server.".to_string(),
+        _ => "This command couldn't be run. >_<!".to_string(),
+    };
+
+    say_error_with_embed(&ctx.http, msg.channel_id, "Command failed", &description).await;
+}
+
+// Command named `name`, aliases are not resolved
+pub fn command_named(name: &str) -> Option<&'static FrameworkCommand> {
+    GENERAL_GROUP
+        .options
+        .commands
+        .iter()
+        .find(|cmd| cmd.options.names[0] == name)
+        .copied()
+}
+
+// `msg` made to look like `user` sent the command, for the commands not sent as a message(slash commands, buttons).
+// the permission checks see the user and their roles
+pub async fn message_as_user(
+    ctx: &Context,
+    mut msg: Message,
+    user: &User,
+    member: Option<&Member>,
+    guild_id: Option<GuildId>,
+    command_name: &str,
+    args: &str,
+) -> Message {
+    msg.author = user.clone();
+    msg.guild_id = guild_id;
+    msg.member = member
+        .and_then(|member| serde_json::to_value(member).ok())
+        .and_then(|member| serde_json::from_value(member).ok());
+    msg.content = format!(
+        "{}{} {}",
+        prefix_of(ctx, guild_id.map(|guild_id| guild_id.0)).await,
+        command_name,
+        args
+    );
+
+    msg
+}
+
+// Run the command through the same hooks as when it's sent as a message
+pub async fn run_command(ctx: &Context, msg: &Message, cmd: &FrameworkCommand, args: &str) {
+    let command_name = cmd.options.names[0];
+    if !before(ctx, msg, command_name).await {
+        return;
+    }
+
+    let result = (cmd.fun)(ctx, msg, Args::new(args, &[Delimiter::Single(' ')])).await;
+    after(ctx, msg, command_name, result).await;
+}
@@ -3,15 +3,21 @@ pub use {
     humantime::format_duration,
     once_cell::sync::OnceCell,
     rand::seq::SliceRandom,
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
     serde_json::{json, Value},
     serenity::{
         async_trait,
-        client::{Client, Context, EventHandler},
+        builder::{
+            CreateApplicationCommandOption, CreateApplicationCommands, CreateComponents,
+            CreateEmbed, CreateEmbedAuthor,
+        },
+        client::{bridge::gateway::ShardManager, Client, Context, EventHandler},
         framework::{
             standard::{
                 help_commands,
                 macros::{command, group, help, hook},
-                Args, CommandGroup, CommandResult, HelpOptions,
+                Args, Command as FrameworkCommand, CommandGroup, CommandResult, Delimiter,
+                DispatchError, HelpOptions,
             },
             StandardFramework,
         },
@@ -19,9 +25,20 @@ pub use {
         model::{
             channel::Message,
             gateway::Ready,
-            guild::Guild,
-            id::{ChannelId, RoleId, UserId},
+            guild::{Guild, Member},
+            id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+            interactions::{
+                application_command::{
+                    ApplicationCommand, ApplicationCommandInteraction,
+                    ApplicationCommandInteractionDataOption, ApplicationCommandOptionType,
+                },
+                autocomplete::AutocompleteInteraction,
+                message_component::{ButtonStyle, InteractionMessage, MessageComponentInteraction},
+                Interaction, InteractionResponseType,
+            },
             misc::Mentionable,
+            user::User,
+            voice::VoiceState,
         },
         prelude::{TypeMap, TypeMapKey},
         Result as SerenityResult,
@@ -29,23 +46,31 @@ pub use {
     songbird::{
         create_player,
         driver::{CryptoMode, DecodeMode},
+        error::JoinError,
         id::ChannelId as VoiceChannelId,
-        input::{restartable::Restartable, Codec, Input},
+        input::{error::Error as InputError, restartable::Restartable, Codec, Input},
         tracks::{PlayMode, TrackHandle},
         Call, Config, CoreEvent, Event, EventContext, EventHandler as VoiceEventHandler,
         SerenityInit, Songbird, TrackEvent,
     },
     std::{
-        collections::{HashMap, HashSet},
-        env,
+        collections::{HashMap, HashSet, VecDeque},
+        env, fmt, fs,
+        future::Future,
         io::Read,
-        path::Path,
+        path::{Path, PathBuf},
         process::{Command, Stdio},
-        sync::{Arc, Mutex},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex,
+        },
         time::Duration,
     },
-    tokio::sync::RwLock,
+    tokio::{
+        sync::{oneshot, RwLock},
+        time::sleep,
+    },
     url::Url,
     wait_timeout::ChildExt,
-    ytdl_rs::{YoutubeDl, YoutubeDlOutput},
+    ytdl_rs::{BackendKind, Error as YtdlError, Playlist, YoutubeDl, YoutubeDlOutput},
 };
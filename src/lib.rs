@@ -1,4 +1,17 @@
+pub mod announce;
 pub mod commands;
 pub mod define;
+pub mod error;
 pub mod events;
+pub mod hooks;
 pub mod import;
+pub mod panel;
+pub mod permission;
+pub mod player;
+pub mod playlist;
+pub mod resolver;
+pub mod settings;
+pub mod shutdown;
+pub mod slash;
+pub mod snapshot;
+pub mod watchdog;
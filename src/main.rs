@@ -1,69 +1,9 @@
 use sympho::define::*;
+use sympho::hooks::*;
 use sympho::import::*;
-
-// Check user can use command
-#[hook]
-async fn before(ctx: &Context, msg: &Message, command_name: &str) -> bool {
-    if EXCLUDE_HOOK.contains(&command_name) {
-        return true;
-    }
-
-    let guild = if let Some(g) = msg.guild(&ctx.cache) {
-        g
-    } else {
-        return false;
-    };
-    let _guild_id = guild.id;
-
-    if !check_user_can_use_command(&guild, ctx, msg).await {
-        return false;
-    };
-
-    let manager = if let Some(m) = songbird::get(ctx).await {
-        m
-    } else {
-        check_msg(
-            msg.reply(
-                ctx,
-                "The bot have something problem, please contact to developer. >_<!",
-            )
-            .await,
-        );
-        return false;
-    };
-
-    if check_bot_using_at_other_chan(&manager, &guild, msg, ctx).await {
-        match command_name {
-            "join" => {
-                let connect_to = if let Some(c) = guild
-                    .voice_states
-                    .get(&msg.author.id)
-                    .and_then(|voice_state| voice_state.channel_id)
-                {
-                    c
-                } else {
-                    check_msg(
-                            msg.reply(ctx, "The bot could'nt get the voice channel information, please contact to developer. >_<!")
-                                .await,
-                        );
-                    return false;
-                };
-
-                check_msg(
-                    msg.reply(
-                        &ctx.http,
-                        &format!("The bot is currently playing at {}", connect_to.mention()),
-                    )
-                    .await,
-                );
-            }
-            _ => {}
-        }
-        return false;
-    }
-
-    true
-}
+use sympho::playlist::*;
+use sympho::settings::*;
+use sympho::shutdown::*;
 
 #[tokio::main]
 async fn main() {
@@ -73,7 +13,11 @@ async fn main() {
 
     let http = Http::new_with_token(&token);
 
+    // the slash commands need the application id
+    let mut application_id = None;
+
     if let Ok(bot_info) = http.get_current_application_info().await {
+        application_id = Some(bot_info.id.0);
         SYMPHO_ICON.get_or_init(|| {
             if let Some(icon_url) = bot_info.icon.clone() {
                 Mutex::new(format!(
@@ -89,25 +33,24 @@ async fn main() {
             .get_or_init(|| Mutex::new(env::var("SYMPHO_PREFIX").unwrap_or("!".to_string())));
     }
 
-    let prefix = if let Ok(sympho_prefix) = SYMPHO_PREFIX
-        .get_or_init(|| Mutex::new("!".to_string()))
-        .lock()
-    {
-        (*sympho_prefix).clone()
-    } else {
-        "!".to_string()
-    };
-
+    // the prefix can be changed per guild, so every prefix comes from `dynamic_prefix`
     let framework = StandardFramework::new()
-        .configure(|c| c.prefix(&prefix))
+        .configure(|c| c.prefix("").dynamic_prefix(dynamic_prefix))
         .before(before)
+        .after(after)
+        .on_dispatch_error(dispatch_error)
         .group(&GENERAL_GROUP);
 
     let songbird_config = Config::default()
         .crypto_mode(CryptoMode::Lite)
         .decode_mode(DecodeMode::Pass);
 
-    let mut client = Client::builder(&token)
+    let mut client_builder = Client::builder(&token);
+    if let Some(application_id) = application_id {
+        client_builder = client_builder.application_id(application_id);
+    }
+
+    let mut client = client_builder
         .event_handler(Handler)
         .framework(framework)
         .register_songbird_from_config(songbird_config)
@@ -116,9 +59,18 @@ async fn main() {
 
     {
         let mut data = client.data.write().await;
-        data.insert::<SymphoGlobal>(Arc::new(RwLock::new(HashMap::default())));
+        data.insert::<SymphoGlobal>(load_sympho_map());
+        data.insert::<SymphoPlaylists>(load_playlists());
     }
 
+    // leave the voice channels and save the state before going down
+    let data = client.data.clone();
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        shutdown(&data, &shard_manager).await;
+    });
+
     let _ = client
         .start()
         .await
@@ -0,0 +1,384 @@
+use crate::define::*;
+use crate::hooks::*;
+use crate::import::*;
+
+// How often the position on the panel is refreshed
+pub const PANEL_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+// How much the volume buttons change the volume(0.1 ~ 100.0)
+pub const VOLUME_STEP: f32 = 10.0;
+
+// Custom ids of the buttons start with this
+const BUTTON_PREFIX: &str = "sympho:";
+
+// What the panel shows, built before the message so no lock is held while sending it
+pub struct PanelView {
+    pub track: Option<TrackSympho>,
+    pub position: Option<Duration>,
+    pub paused: bool,
+    pub loop_mode: LoopMode,
+    pub volume: f32,
+    pub queue_len: usize,
+}
+
+// State of the guild for the panel, `get_info` is asked without the lock
+pub async fn panel_view(sympho_global_mutex: &SymphoMap, key: u64) -> PanelView {
+    let (current, loop_mode, volume, queue_len) = {
+        let sympho_global = sympho_global_mutex.read().await;
+        if let Some(sympho_data) = sympho_global.get(&key) {
+            (
+                sympho_data.current.clone(),
+                sympho_data.loop_mode,
+                sympho_data.volume,
+                sympho_data.queue.len(),
+            )
+        } else {
+            (None, LoopMode::Off, 1.0, 0)
+        }
+    };
+
+    let (track, position, paused) = if let Some((track_handle, track)) = current {
+        let info = track_handle.get_info().await.ok();
+        (
+            Some(track),
+            info.as_ref().map(|info| info.position),
+            info.map_or(false, |info| info.playing == PlayMode::Pause),
+        )
+    } else {
+        (None, None, false)
+    };
+
+    PanelView {
+        track,
+        position,
+        paused,
+        loop_mode,
+        volume,
+        queue_len,
+    }
+}
+
+fn panel_embed<'a>(e: &'a mut CreateEmbed, view: &PanelView) -> &'a mut CreateEmbed {
+    e.author(sympho_author);
+
+    if let Some(track) = &view.track {
+        e.title(track.title.clone());
+        e.url(&track.url);
+        e.description(format!(
+            "{}{} / {}",
+            if view.paused { "(paused) " } else { "" },
+            view.position
+                .map(dur_to_hhmmss)
+                .unwrap_or_else(|| "Unknown".to_string()),
+            if track.is_live {
+                "Live".to_string()
+            } else {
+                dur_to_hhmmss(track.duration)
+            }
+        ));
+        if let Some(thumb_url) = &track.thumb {
+            e.thumbnail(thumb_url);
+        }
+    } else {
+        e.title("No songs.");
+    }
+
+    e.footer(|f| {
+        f.text(format!(
+            "Loop: {} | Volume: {} | Queue: {} songs",
+            view.loop_mode,
+            view.volume * 100.0,
+            view.queue_len
+        ))
+    });
+
+    e
+}
+
+fn panel_buttons<'a>(c: &'a mut CreateComponents, view: &PanelView) -> &'a mut CreateComponents {
+    let button = |row: &mut serenity::builder::CreateActionRow, id: &str, label: &str| {
+        row.create_button(|b| {
+            b.custom_id(format!("{}{}", BUTTON_PREFIX, id))
+                .label(label)
+                .style(ButtonStyle::Secondary)
+        });
+    };
+
+    c.create_action_row(|row| {
+        button(row, "previous", "⏮");
+        button(row, "pause", if view.paused { "▶" } else { "⏸" });
+        button(row, "skip", "⏭");
+        button(row, "shuffle", "🔀");
+        row
+    });
+    c.create_action_row(|row| {
+        button(
+            row,
+            "loop",
+            match view.loop_mode {
+                LoopMode::Off => "➡",
+                LoopMode::Track => "🔂",
+                LoopMode::Queue => "🔁",
+            },
+        );
+        button(row, "volume_down", "🔉");
+        button(row, "volume_up", "🔊");
+        row
+    });
+
+    c
+}
+
+// Post a new panel to `chan_id`, the old panel of the guild is deleted
+pub async fn post_panel(
+    http: &Arc<Http>,
+    sympho_global_mutex: &SymphoMap,
+    key: u64,
+    chan_id: ChannelId,
+) {
+    let view = panel_view(sympho_global_mutex, key).await;
+
+    let message = match chan_id
+        .send_message(http, |m| {
+            m.content("_nowplaying ♡:_");
+            m.embed(|e| panel_embed(e, &view));
+            m.components(|c| panel_buttons(c, &view));
+            m
+        })
+        .await
+    {
+        Ok(message) => message,
+        Err(why) => {
+            println!("Error sending message: {:?}", why);
+            return;
+        }
+    };
+
+    let old_panel = {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, key);
+        sympho_data.panel.replace((chan_id, message.id))
+    };
+
+    if let Some((old_chan_id, old_message_id)) = old_panel {
+        let _ = old_chan_id.delete_message(http, old_message_id).await;
+    }
+
+    spawn_panel_refresh(http.clone(), sympho_global_mutex.clone(), key, message.id);
+}
+
+// Update the panel of the guild, if there is one
+pub async fn refresh_panel(http: &Arc<Http>, sympho_global_mutex: &SymphoMap, key: u64) {
+    let panel = sympho_global_mutex
+        .read()
+        .await
+        .get(&key)
+        .and_then(|sympho_data| sympho_data.panel);
+    let (chan_id, message_id) = if let Some(panel) = panel {
+        panel
+    } else {
+        return;
+    };
+
+    let view = panel_view(sympho_global_mutex, key).await;
+    if let Err(why) = chan_id
+        .edit_message(http, message_id, |m| {
+            m.embed(|e| panel_embed(e, &view));
+            m.components(|c| panel_buttons(c, &view));
+            m
+        })
+        .await
+    {
+        println!("Couldn't update the panel: {:?}", why);
+        // deleted by someone, stop updating it
+        let mut sympho_global = sympho_global_mutex.write().await;
+        if let Some(sympho_data) = sympho_global.get_mut(&key) {
+            if sympho_data.panel == Some((chan_id, message_id)) {
+                sympho_data.panel = None;
+            }
+        }
+    }
+}
+
+// Keep the position on the panel up to date while the song is playing,
+// stops when the panel is replaced or gone
+fn spawn_panel_refresh(
+    http: Arc<Http>,
+    sympho_global_mutex: SymphoMap,
+    key: u64,
+    message_id: MessageId,
+) {
+    tokio::spawn(async move {
+        loop {
+            sleep(PANEL_REFRESH_INTERVAL).await;
+
+            let (is_current_panel, is_playing) = sympho_global_mutex
+                .read()
+                .await
+                .get(&key)
+                .map(|sympho_data| {
+                    (
+                        sympho_data.panel.map(|(_, id)| id) == Some(message_id),
+                        sympho_data.current.is_some(),
+                    )
+                })
+                .unwrap_or((false, false));
+
+            if !is_current_panel {
+                break;
+            }
+            // the track events update it when the song changes
+            if is_playing {
+                refresh_panel(&http, &sympho_global_mutex, key).await;
+            }
+        }
+    });
+}
+
+// Command and its arguments the button runs
+fn command_of_button(button: &str, view: &PanelView) -> Option<(&'static str, String)> {
+    let command = match button {
+        "previous" => ("previous", String::new()),
+        "pause" if view.paused => ("resume", String::new()),
+        "pause" => ("pause", String::new()),
+        "skip" => ("skip", String::new()),
+        "shuffle" => ("shuffle", String::new()),
+        "loop" => (
+            "looping",
+            match view.loop_mode {
+                LoopMode::Off => LoopMode::Track,
+                LoopMode::Track => LoopMode::Queue,
+                LoopMode::Queue => LoopMode::Off,
+            }
+            .to_string(),
+        ),
+        "volume_down" => (
+            "volume",
+            (view.volume * 100.0 - VOLUME_STEP).max(0.1).to_string(),
+        ),
+        "volume_up" => (
+            "volume",
+            (view.volume * 100.0 + VOLUME_STEP).min(100.0).to_string(),
+        ),
+        _ => return None,
+    };
+
+    Some(command)
+}
+
+// A button of the panel was pressed, it runs the command with the same checks as the text command
+pub async fn press_button(ctx: &Context, interaction: &MessageComponentInteraction) {
+    let button = if let Some(button) = interaction.data.custom_id.strip_prefix(BUTTON_PREFIX) {
+        button
+    } else {
+        return;
+    };
+    let guild_id = if let Some(guild_id) = interaction.guild_id {
+        guild_id
+    } else {
+        return;
+    };
+    let msg = if let InteractionMessage::Regular(msg) = &interaction.message {
+        msg.clone()
+    } else {
+        return;
+    };
+
+    // the panel is updated after the command, nothing else to respond
+    if let Err(why) = interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::DeferredUpdateMessage)
+        })
+        .await
+    {
+        println!("Couldn't respond to the button: {:?}", why);
+        return;
+    }
+
+    let sympho_global_mutex = {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            sympho_global_mutex.clone()
+        } else {
+            return;
+        }
+    };
+
+    let view = panel_view(&sympho_global_mutex, guild_id.0).await;
+    let (command_name, args) = if let Some(command) = command_of_button(button, &view) {
+        command
+    } else {
+        return;
+    };
+    let cmd = if let Some(cmd) = command_named(command_name) {
+        cmd
+    } else {
+        return;
+    };
+
+    let msg = message_as_user(
+        ctx,
+        msg,
+        &interaction.user,
+        interaction.member.as_ref(),
+        Some(guild_id),
+        command_name,
+        &args,
+    )
+    .await;
+    run_command(ctx, &msg, cmd, &args).await;
+
+    refresh_panel(&ctx.http, &sympho_global_mutex, guild_id.0).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(paused: bool, loop_mode: LoopMode, volume: f32) -> PanelView {
+        PanelView {
+            track: None,
+            position: None,
+            paused,
+            loop_mode,
+            volume,
+            queue_len: 0,
+        }
+    }
+
+    #[test]
+    fn buttons_run_commands() {
+        assert_eq!(
+            command_of_button("pause", &view(false, LoopMode::Off, 1.0)),
+            Some(("pause", String::new()))
+        );
+        assert_eq!(
+            command_of_button("pause", &view(true, LoopMode::Off, 1.0)),
+            Some(("resume", String::new()))
+        );
+        assert_eq!(
+            command_of_button("loop", &view(false, LoopMode::Queue, 1.0)),
+            Some(("looping", "off".to_string()))
+        );
+        assert_eq!(
+            command_of_button("volume_up", &view(false, LoopMode::Off, 0.95)),
+            Some(("volume", "100".to_string()))
+        );
+        assert_eq!(
+            command_of_button("volume_down", &view(false, LoopMode::Off, 0.05)),
+            Some(("volume", "0.1".to_string()))
+        );
+        assert_eq!(
+            command_of_button("unknown", &view(false, LoopMode::Off, 1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn button_commands_exist() {
+        for button in &["previous", "pause", "skip", "shuffle", "loop", "volume_up"] {
+            let (command_name, _) =
+                command_of_button(button, &view(false, LoopMode::Off, 1.0)).unwrap();
+            assert!(command_named(command_name).is_some());
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use crate::define::*;
+use crate::import::*;
+
+// Who can use a command, a higher level can use the lower level commands too
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionLevel {
+    Everyone,
+    Dj,
+    Admin,
+}
+
+impl fmt::Display for PermissionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Everyone => write!(f, "everyone"),
+            Self::Dj => write!(f, "dj"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for PermissionLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "everyone" => Ok(Self::Everyone),
+            "dj" => Ok(Self::Dj),
+            "admin" => Ok(Self::Admin),
+            _ => Err(()),
+        }
+    }
+}
+
+// Commands everyone can use on default, they don't change the playback
+pub const EVERYONE_COMMANDS: &[&str] = &["help", "queue", "current", "history", "voteskip"];
+// Commands only admin can use on default, they change the settings of the guild
+pub const ADMIN_COMMANDS: &[&str] = &["permission", "limits", "config"];
+// Actions(the first argument) that only show something, everyone can use them at any command level
+pub const EVERYONE_ACTIONS: &[(&str, &str)] = &[("playlist", "list"), ("playlist", "show")];
+
+// Permission settings of a guild
+// roles are role names or role ids
+// `allow_when_alone` lets everyone use the dj commands when they are alone with the bot
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Permissions {
+    pub dj_roles: Vec<String>,
+    pub admin_roles: Vec<String>,
+    pub command_levels: HashMap<String, PermissionLevel>,
+    pub allow_when_alone: bool,
+}
+
+// roles separated by comma, e.g. `SYMPHO_DJ_ROLES=DJUser,Music`
+fn roles_from_env(key: &str) -> Option<Vec<String>> {
+    env::var(key).ok().map(|roles| {
+        roles
+            .split(',')
+            .map(|role| role.trim().to_string())
+            .filter(|role| !role.is_empty())
+            .collect()
+    })
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            dj_roles: roles_from_env("SYMPHO_DJ_ROLES").unwrap_or(vec!["DJUser".to_string()]),
+            admin_roles: roles_from_env("SYMPHO_ADMIN_ROLES").unwrap_or_default(),
+            command_levels: HashMap::new(),
+            allow_when_alone: true,
+        }
+    }
+}
+
+impl Permissions {
+    // Level needed for `command_name`, aliases are resolved to the command
+    pub fn level_of(&self, command_name: &str) -> PermissionLevel {
+        let name = canonical_command_name(command_name).unwrap_or(command_name);
+
+        if let Some(level) = self.command_levels.get(name) {
+            *level
+        } else if EVERYONE_COMMANDS.contains(&name) {
+            PermissionLevel::Everyone
+        } else if ADMIN_COMMANDS.contains(&name) {
+            PermissionLevel::Admin
+        } else {
+            PermissionLevel::Dj
+        }
+    }
+
+    // Level needed for `action` of `command_name`, e.g. `playlist show`
+    pub fn level_of_action(&self, command_name: &str, action: Option<&str>) -> PermissionLevel {
+        let name = canonical_command_name(command_name).unwrap_or(command_name);
+
+        match action {
+            Some(action) if EVERYONE_ACTIONS.contains(&(name, action)) => PermissionLevel::Everyone,
+            _ => self.level_of(command_name),
+        }
+    }
+
+    // Level of the user who has `roles`, the guild owner and administrators are admin
+    pub fn level_of_user(
+        &self,
+        guild: &Guild,
+        user_id: UserId,
+        roles: &[RoleId],
+    ) -> PermissionLevel {
+        let roles = roles
+            .iter()
+            .filter_map(|role_id| guild.roles.get(role_id))
+            .collect::<Vec<_>>();
+        let has_any = |names: &[String]| {
+            roles.iter().any(|role| {
+                names
+                    .iter()
+                    .any(|name| *name == role.name || *name == role.id.0.to_string())
+            })
+        };
+
+        if guild.owner_id == user_id
+            || roles.iter().any(|role| role.permissions.administrator())
+            || has_any(&self.admin_roles)
+        {
+            PermissionLevel::Admin
+        } else if has_any(&self.dj_roles) {
+            PermissionLevel::Dj
+        } else {
+            PermissionLevel::Everyone
+        }
+    }
+
+    // Describe the settings for `permission` command
+    pub fn describe(&self) -> String {
+        let or_none = |roles: &[String]| {
+            if roles.is_empty() {
+                "(none)".to_string()
+            } else {
+                roles
+                    .iter()
+                    .map(|role| format!("`{}`", role))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        };
+        let mut command_levels = self
+            .command_levels
+            .iter()
+            .map(|(name, level)| format!("`{}`: {}", name, level))
+            .collect::<Vec<_>>();
+        command_levels.sort();
+
+        format!(
+            "DJ roles: {}\nAdmin roles: {}\nEveryone can use the DJ commands when alone with the bot: {}\nChanged command levels: {}",
+            or_none(&self.dj_roles),
+            or_none(&self.admin_roles),
+            if self.allow_when_alone { "on" } else { "off" },
+            if command_levels.is_empty() {
+                "(none)".to_string()
+            } else {
+                command_levels.join(", ")
+            }
+        )
+    }
+}
+
+// Permission settings of the guild
+pub async fn permissions_of(ctx: &Context, key: u64) -> Permissions {
+    let data = ctx.data.read().await;
+    if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+        if let Some(sympho_data) = sympho_global_mutex.read().await.get(&key) {
+            return sympho_data.settings.permissions.clone();
+        }
+    }
+
+    Permissions::default()
+}
+
+// `<@&id>` is stored as the id, the others as the role name
+pub fn parse_role(role: &str) -> String {
+    role.strip_prefix("<@&")
+        .and_then(|role| role.strip_suffix('>'))
+        .unwrap_or(role)
+        .to_string()
+}
+
+// First argument of the command in `content`, lowercased. flags like `--mine` are skipped
+pub fn action_of(content: &str, prefix: &str) -> Option<String> {
+    content
+        .strip_prefix(prefix)?
+        .split_whitespace()
+        .skip(1)
+        .find(|arg| !arg.starts_with("--"))
+        .map(|action| action.to_lowercase())
+}
+
+// Name of the command that has `name` as its name or alias
+pub fn canonical_command_name(name: &str) -> Option<&'static str> {
+    GENERAL_GROUP
+        .options
+        .commands
+        .iter()
+        .find(|cmd| cmd.options.names.contains(&name))
+        .map(|cmd| cmd.options.names[0])
+}
+
+// Whether the user is the only listener in the voice channel of the bot.
+// false if the bot is not in a voice channel
+pub fn is_alone_with_bot(guild: &Guild, user_id: UserId, bot_channel: Option<ChannelId>) -> bool {
+    let user_channel = guild
+        .voice_states
+        .get(&user_id)
+        .and_then(|voice_state| voice_state.channel_id);
+
+    is_only_listener(user_id, user_channel, bot_channel, |channel_id| {
+        listeners_in(guild, channel_id)
+    })
+}
+
+// `is_alone_with_bot` with the channel of the user and the listeners of a channel given
+fn is_only_listener(
+    user_id: UserId,
+    user_channel: Option<ChannelId>,
+    bot_channel: Option<ChannelId>,
+    listeners_of: impl FnOnce(ChannelId) -> HashSet<UserId>,
+) -> bool {
+    let channel_id = if let Some(channel_id) = bot_channel {
+        channel_id
+    } else {
+        return false;
+    };
+    if user_channel != Some(channel_id) {
+        return false;
+    }
+
+    let listeners = listeners_of(channel_id);
+    listeners.len() == 1 && listeners.contains(&user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_levels() {
+        let mut permissions = Permissions::default();
+
+        assert_eq!(permissions.level_of("help"), PermissionLevel::Everyone);
+        assert_eq!(permissions.level_of("q"), PermissionLevel::Everyone);
+        assert_eq!(permissions.level_of("play"), PermissionLevel::Dj);
+        assert_eq!(permissions.level_of("p"), PermissionLevel::Dj);
+        assert_eq!(permissions.level_of("limits"), PermissionLevel::Admin);
+
+        permissions
+            .command_levels
+            .insert("play".to_string(), PermissionLevel::Everyone);
+        assert_eq!(permissions.level_of("p"), PermissionLevel::Everyone);
+    }
+
+    #[test]
+    fn action_levels() {
+        let permissions = Permissions::default();
+
+        assert_eq!(action_of("!pl Show chill", "!").as_deref(), Some("show"));
+        assert_eq!(action_of("!playlist", "!"), None);
+        assert_eq!(action_of("!pl --mine list", "!").as_deref(), Some("list"));
+        assert_eq!(
+            permissions.level_of_action("pl", Some("show")),
+            PermissionLevel::Everyone
+        );
+        assert_eq!(
+            permissions.level_of_action("playlist", Some("list")),
+            PermissionLevel::Everyone
+        );
+        assert_eq!(
+            permissions.level_of_action("playlist", Some("save")),
+            PermissionLevel::Dj
+        );
+        assert_eq!(
+            permissions.level_of_action("play", Some("show")),
+            PermissionLevel::Dj
+        );
+    }
+
+    #[test]
+    fn alone_only_with_the_bot() {
+        let user = UserId(1);
+        let only_user = |_| vec![user].into_iter().collect::<HashSet<_>>();
+
+        assert!(is_only_listener(
+            user,
+            Some(ChannelId(10)),
+            Some(ChannelId(10)),
+            only_user
+        ));
+        // the bot is not in a voice channel
+        assert!(!is_only_listener(
+            user,
+            Some(ChannelId(10)),
+            None,
+            only_user
+        ));
+        assert!(!is_only_listener(user, None, None, |_| HashSet::new()));
+        // the user is in another channel
+        assert!(!is_only_listener(
+            user,
+            Some(ChannelId(11)),
+            Some(ChannelId(10)),
+            only_user
+        ));
+        assert!(!is_only_listener(
+            user,
+            Some(ChannelId(10)),
+            Some(ChannelId(10)),
+            |_| vec![user, UserId(2)].into_iter().collect()
+        ));
+    }
+
+    #[test]
+    fn level_round_trip() {
+        for level in &[
+            PermissionLevel::Everyone,
+            PermissionLevel::Dj,
+            PermissionLevel::Admin,
+        ] {
+            assert_eq!(level.to_string().parse::<PermissionLevel>(), Ok(*level));
+        }
+        assert!(PermissionLevel::Everyone < PermissionLevel::Dj);
+        assert!(PermissionLevel::Dj < PermissionLevel::Admin);
+    }
+}
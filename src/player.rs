@@ -0,0 +1,239 @@
+use crate::define::*;
+use crate::error::*;
+use crate::events::*;
+use crate::import::*;
+use crate::shutdown::*;
+use crate::watchdog::*;
+
+// How many times the driver tries to open one source before dropping the track
+pub const SOURCE_RETRIES: u32 = 3;
+// Wait before the first retry, doubled for each following retry
+pub const SOURCE_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+// Open the source of `url`, retrying with a bounded backoff.
+// Streams often fail once on a flaky network, so one failure shouldn't drop the track.
+pub async fn get_source_with_retry(url: &str) -> Result<Input, InputError> {
+    let mut backoff = SOURCE_RETRY_BACKOFF;
+    let mut attempt = 1;
+
+    loop {
+        match get_source(url.to_string()).await {
+            Ok(source) => return Ok(source),
+            Err(why) if attempt < SOURCE_RETRIES => {
+                println!(
+                    "Couldn't open `{}` (attempt {}/{}): {:?}",
+                    url, attempt, SOURCE_RETRIES, why
+                );
+                sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(why) => return Err(why),
+        }
+    }
+}
+
+// Join `channel_id` and register the track events, the events are announced to `chan_id`
+pub async fn connect(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    chan_id: ChannelId,
+) -> Result<Arc<serenity::prelude::Mutex<Call>>, SymphoError> {
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return Err(SymphoError::NoSongbird);
+    };
+
+    let (handle_lock, success) = manager.join(guild_id, channel_id).await;
+    if success.is_err() {
+        return Err(SymphoError::JoinFailed);
+    }
+
+    {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+            sympho_data.text_channel = Some(chan_id);
+        }
+    }
+
+    let send_http = ctx.http.clone();
+
+    let mut handle = handle_lock.lock().await;
+
+    // `join` returns the same call when the bot is already in the guild(e.g. moved by `join`),
+    // its handlers are replaced so each event is handled once, with the new text channel
+    handle.remove_all_global_events();
+    handle.add_global_event(
+        Event::Track(TrackEvent::Play),
+        TrackStartNotifier {
+            data: ctx.data.clone(),
+            key: guild_id.0,
+            chan_id,
+            http: send_http.clone(),
+        },
+    );
+
+    handle.add_global_event(
+        Event::Track(TrackEvent::End),
+        TrackEndNotifier {
+            handler: handle_lock.clone(),
+            data: ctx.data.clone(),
+            key: guild_id.0,
+            chan_id,
+            http: send_http.clone(),
+        },
+    );
+
+    drop(handle);
+
+    // nothing may be played after joining
+    watch_idle(&ctx.data, &ctx.http, guild_id.0).await;
+
+    Ok(handle_lock)
+}
+
+// Leave the voice channel of the guild and drop its songs.
+// the panel is deleted since its buttons do nothing without the bot
+pub async fn leave_guild(
+    http: &Arc<Http>,
+    manager: &Songbird,
+    sympho_global_mutex: &SymphoMap,
+    guild_id: GuildId,
+) -> Result<(), JoinError> {
+    manager.remove(guild_id).await?;
+
+    let (panel, announcement) = {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+        sympho_data.clear_queue();
+        sympho_data.starting = None;
+        sympho_data.volume = sympho_data.settings.volume;
+        sympho_data.idle_ticket = None;
+        sympho_data.alone_ticket = None;
+        sympho_data.paused_alone = false;
+
+        if let Some((current, _)) = sympho_data.current.take() {
+            let _ = current.stop();
+        }
+
+        (
+            sympho_data.panel.take(),
+            sympho_data
+                .announcement
+                .take()
+                .filter(|_| sympho_data.settings.announce_cleanup),
+        )
+    };
+
+    for (chan_id, message_id) in panel.into_iter().chain(announcement) {
+        let _ = chan_id.delete_message(http, message_id).await;
+    }
+
+    Ok(())
+}
+
+// Playback driver, start the first playable track of the queue.
+// Tracks whose source can't be opened are dropped and the driver falls through to the next one,
+// returns the dropped tracks with the reason so the caller can tell the text channel.
+// Opening a source can take a while, so no lock is held meanwhile,
+// `starting` keeps a second driver(e.g. `play` racing with the track end event) from starting too.
+pub async fn start_next(
+    handler_lock: &Arc<serenity::prelude::Mutex<Call>>,
+    sympho_global_mutex: &SymphoMap,
+    key: u64,
+) -> Vec<(TrackSympho, String)> {
+    let mut dropped = vec![];
+
+    loop {
+        let (ticket, track) = {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, key);
+
+            if sympho_data.current.is_some() || sympho_data.starting.is_some() || is_shutting_down()
+            {
+                return dropped;
+            }
+
+            // placeholders keep their position, the driver waits for them to be resolved
+            match sympho_data.queue.first() {
+                Some(track) if track.pending.is_none() => {}
+                _ => return dropped,
+            }
+
+            let track = sympho_data.queue.remove(0);
+            sympho_data.queue_duration -= track.duration;
+
+            let ticket = next_ticket();
+            sympho_data.starting = Some(ticket);
+
+            (ticket, track)
+        };
+
+        match get_source_with_retry(&track.url).await {
+            Ok(source) => {
+                let mut handler = handler_lock.lock().await;
+                let mut sympho_global = sympho_global_mutex.write().await;
+                let sympho_data = guild_entry(&mut sympho_global, key);
+
+                // `stop` or `leave` ran while the source was opening
+                if sympho_data.starting != Some(ticket) {
+                    return dropped;
+                }
+                sympho_data.starting = None;
+
+                let track_handle = play_from_source(&mut handler, source, sympho_data.volume);
+                if sympho_data.loop_mode == LoopMode::Track {
+                    if let Err(why) = track_handle.enable_loop() {
+                        println!("Couldn't loop `{}`: {:?}", track.url, why);
+                    }
+                }
+                if track.start > Duration::default() {
+                    if let Err(why) = track_handle.seek_time(track.start) {
+                        println!("Couldn't seek `{}`: {:?}", track.url, why);
+                    }
+                }
+                sympho_data.current = Some((track_handle, track));
+                sympho_data.announced = false;
+                sympho_data.skip_votes.clear();
+
+                return dropped;
+            }
+            Err(why) => {
+                let mut sympho_global = sympho_global_mutex.write().await;
+                if let Some(sympho_data) = sympho_global.get_mut(&key) {
+                    if sympho_data.starting != Some(ticket) {
+                        return dropped;
+                    }
+                    sympho_data.starting = None;
+                }
+
+                println!("Couldn't open `{}`: {:?}", track.url, why);
+                dropped.push((track, describe_input_error(&why)));
+            }
+        }
+    }
+}
+
+// Tell the text channel which entries were dropped by the driver
+pub async fn say_dropped_tracks(
+    http: &Arc<Http>,
+    chan_id: ChannelId,
+    dropped: &[(TrackSympho, String)],
+) {
+    if dropped.is_empty() {
+        return;
+    }
+
+    let tracks = dropped
+        .iter()
+        .map(|(track, why)| format!("`{}`: {}", track.title, why))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    say_sympho_error(http, chan_id, &SymphoError::Source(tracks)).await;
+}
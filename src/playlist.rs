@@ -0,0 +1,270 @@
+use crate::define::*;
+use crate::import::*;
+use crate::settings::*;
+
+// Longest name of a saved playlist
+pub const MAX_PLAYLIST_NAME_LEN: usize = 32;
+
+// Songs saved by a user, in a guild(everyone in the guild can load it) or for themselves.
+// only the owner can edit it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedPlaylist {
+    pub owner: u64,
+    pub tracks: Vec<TrackSympho>,
+}
+
+impl SavedPlaylist {
+    pub fn new(owner: UserId, tracks: Vec<TrackSympho>) -> Self {
+        Self {
+            owner: owner.0,
+            tracks: tracks.into_iter().map(saved_track).collect(),
+        }
+    }
+
+    // The owner, or the admins of the guild for the playlists of the guild
+    pub fn can_edit(&self, scope: PlaylistScope, user_id: UserId, is_admin: bool) -> bool {
+        self.owner == user_id.0 || (is_admin && matches!(scope, PlaylistScope::Guild(_)))
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.tracks.iter().map(|track| track.duration).sum()
+    }
+}
+
+// The song as it's saved, who requested it is not a part of the playlist
+pub fn saved_track(track: TrackSympho) -> TrackSympho {
+    TrackSympho {
+        pending: None,
+        requester: None,
+        ..track
+    }
+}
+
+// The current song and the queue, without the placeholders
+pub fn saved_tracks_of(sympho_data: &SymphoData) -> Vec<TrackSympho> {
+    sympho_data
+        .current
+        .iter()
+        .map(|(_, track)| track)
+        .chain(sympho_data.queue.iter())
+        .filter(|track| track.pending.is_none())
+        .cloned()
+        .map(|track| TrackSympho {
+            // the current song may have been seeked by `seek`, it's saved from the beginning
+            start: Duration::default(),
+            ..saved_track(track)
+        })
+        .collect()
+}
+
+// Names are case insensitive, and letters, digits, `-` and `_` only
+pub fn parse_playlist_name(name: &str) -> Option<String> {
+    if name.is_empty()
+        || name.chars().count() > MAX_PLAYLIST_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+
+    Some(name.to_lowercase())
+}
+
+// `SYMPHO_PLAYLISTS_PATH`, or `sympho_playlists.json` in the data dir
+pub fn playlists_path() -> PathBuf {
+    data_path("SYMPHO_PLAYLISTS_PATH", "sympho_playlists.json")
+}
+
+// `SYMPHO_USER_PLAYLISTS_PATH`, or `sympho_user_playlists.json` in the data dir
+pub fn user_playlists_path() -> PathBuf {
+    data_path("SYMPHO_USER_PLAYLISTS_PATH", "sympho_user_playlists.json")
+}
+
+// Where a playlist is kept, the guild it was saved in or the user who can load it in any guild
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaylistScope {
+    Guild(u64),
+    User(u64),
+}
+
+// Saved playlists of all guilds and users, by id then by name
+#[derive(Debug, Default)]
+pub struct PlaylistStore {
+    pub guilds: HashMap<u64, HashMap<String, SavedPlaylist>>,
+    pub users: HashMap<u64, HashMap<String, SavedPlaylist>>,
+}
+
+impl PlaylistStore {
+    pub fn playlists(&self, scope: PlaylistScope) -> Option<&HashMap<String, SavedPlaylist>> {
+        match scope {
+            PlaylistScope::Guild(key) => self.guilds.get(&key),
+            PlaylistScope::User(key) => self.users.get(&key),
+        }
+    }
+
+    pub fn playlists_mut(&mut self, scope: PlaylistScope) -> &mut HashMap<String, SavedPlaylist> {
+        match scope {
+            PlaylistScope::Guild(key) => self.guilds.entry(key).or_default(),
+            PlaylistScope::User(key) => self.users.entry(key).or_default(),
+        }
+    }
+
+    pub fn get(&self, scope: PlaylistScope, name: &str) -> Option<&SavedPlaylist> {
+        self.playlists(scope)?.get(name)
+    }
+}
+
+pub type PlaylistMap = Arc<RwLock<PlaylistStore>>;
+
+pub struct SymphoPlaylists;
+
+impl TypeMapKey for SymphoPlaylists {
+    type Value = PlaylistMap;
+}
+
+// Playlists saved by `save_playlists`
+pub fn load_playlists() -> PlaylistMap {
+    Arc::new(RwLock::new(PlaylistStore {
+        guilds: read_json(&playlists_path()),
+        users: read_json(&user_playlists_path()),
+    }))
+}
+
+// Only one write of each file at a time, like `save_settings`
+static PLAYLISTS_WRITE: OnceCell<tokio::sync::Mutex<()>> = OnceCell::new();
+static USER_PLAYLISTS_WRITE: OnceCell<tokio::sync::Mutex<()>> = OnceCell::new();
+
+// Write the playlists of all guilds, or of all users with `users`
+async fn write_playlists(playlist_map: &PlaylistMap, users: bool) {
+    let (write_lock, path) = if users {
+        (&USER_PLAYLISTS_WRITE, user_playlists_path())
+    } else {
+        (&PLAYLISTS_WRITE, playlists_path())
+    };
+    let _write = write_lock.get_or_init(Default::default).lock().await;
+
+    let playlists = {
+        let playlist_store = playlist_map.read().await;
+        let playlists = if users {
+            &playlist_store.users
+        } else {
+            &playlist_store.guilds
+        };
+        playlists
+            .iter()
+            .filter(|(_, playlists)| !playlists.is_empty())
+            .map(|(key, playlists)| (*key, playlists.clone()))
+            .collect::<HashMap<_, _>>()
+    };
+
+    write_json_blocking(path, playlists).await;
+}
+
+// Write the file `scope` is kept in, call it after the lock is released
+pub async fn save_playlists(playlist_map: &PlaylistMap, scope: PlaylistScope) {
+    write_playlists(playlist_map, matches!(scope, PlaylistScope::User(_))).await;
+}
+
+pub async fn save_all_playlists(playlist_map: &PlaylistMap) {
+    write_playlists(playlist_map, false).await;
+    write_playlists(playlist_map, true).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_names() {
+        assert_eq!(
+            parse_playlist_name("Chill_Mix-2"),
+            Some("chill_mix-2".to_string())
+        );
+        assert_eq!(parse_playlist_name(""), None);
+        assert_eq!(parse_playlist_name("a b"), None);
+        assert_eq!(
+            parse_playlist_name(&"a".repeat(MAX_PLAYLIST_NAME_LEN + 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn saved_tracks_skip_placeholders() {
+        let sympho_data = SymphoData {
+            queue: vec![
+                TrackSympho {
+                    url: "a".to_string(),
+                    requester: Some(UserId(1)),
+                    ..Default::default()
+                },
+                TrackSympho {
+                    url: "pending".to_string(),
+                    pending: Some(1),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let playlist = SavedPlaylist::new(UserId(2), saved_tracks_of(&sympho_data));
+        assert_eq!(playlist.tracks.len(), 1);
+        assert_eq!(playlist.tracks[0].url, "a");
+        assert_eq!(playlist.tracks[0].requester, None);
+        let guild = PlaylistScope::Guild(1);
+        assert!(playlist.can_edit(guild, UserId(2), false));
+        assert!(!playlist.can_edit(guild, UserId(1), false));
+        assert!(playlist.can_edit(guild, UserId(1), true));
+    }
+
+    fn playlist_of(owner: u64, url: &str) -> SavedPlaylist {
+        SavedPlaylist::new(
+            UserId(owner),
+            vec![TrackSympho {
+                url: url.to_string(),
+                ..Default::default()
+            }],
+        )
+    }
+
+    #[test]
+    fn names_are_looked_up_per_scope() {
+        let mut playlist_store = PlaylistStore::default();
+        playlist_store
+            .playlists_mut(PlaylistScope::Guild(1))
+            .insert("chill".to_string(), playlist_of(10, "guild"));
+        playlist_store
+            .playlists_mut(PlaylistScope::User(20))
+            .insert("chill".to_string(), playlist_of(20, "user"));
+
+        let url_of = |scope| {
+            playlist_store
+                .get(scope, "chill")
+                .map(|saved| saved.tracks[0].url.clone())
+        };
+        assert_eq!(url_of(PlaylistScope::Guild(1)).as_deref(), Some("guild"));
+        assert_eq!(url_of(PlaylistScope::User(20)).as_deref(), Some("user"));
+        // the user playlists are not the ones of a guild with the same id, and the other way around
+        assert_eq!(url_of(PlaylistScope::Guild(20)), None);
+        assert_eq!(url_of(PlaylistScope::User(1)), None);
+        assert_eq!(url_of(PlaylistScope::User(10)), None);
+    }
+
+    #[test]
+    fn owners_per_scope() {
+        let guild = PlaylistScope::Guild(1);
+        let user = PlaylistScope::User(20);
+        let guild_chill = playlist_of(10, "guild");
+        let user_chill = playlist_of(20, "user");
+
+        // the same name in both scopes, user 20 owns only their own one
+        assert!(user_chill.can_edit(user, UserId(20), false));
+        assert!(!guild_chill.can_edit(guild, UserId(20), false));
+        assert!(guild_chill.can_edit(guild, UserId(10), false));
+
+        // admins of the guild edit the guild playlists, never the ones of a user
+        assert!(guild_chill.can_edit(guild, UserId(20), true));
+        assert!(!user_chill.can_edit(user, UserId(10), true));
+    }
+}
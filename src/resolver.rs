@@ -0,0 +1,510 @@
+use crate::define::*;
+use crate::import::*;
+
+// Queue entry shown while the metadata of `url` is still being resolved
+pub fn placeholder(url: &str, ticket: u64, requester: Option<UserId>) -> TrackSympho {
+    TrackSympho {
+        url: url.to_string(),
+        title: format!("(resolving) {}", url),
+        pending: Some(ticket),
+        requester,
+        ..Default::default()
+    }
+}
+
+// Push a placeholder onto the queue(or the head of it if `at_head`), then run `lookup` in its own task.
+// The queue lock is only held to insert and to replace the placeholder,
+// so a slow youtube-dl or ffprobe call never freezes the other guilds.
+// The lookup is dropped(cancelled) as soon as its sender in `SymphoData.resolving` is dropped.
+// The returned handle yields which tracks took the place of the placeholder,
+// or why the lookup failed(the placeholder is removed then).
+pub async fn spawn_resolution<F>(
+    sympho_global_mutex: SymphoMap,
+    key: u64,
+    url: String,
+    requester: Option<UserId>,
+    at_head: bool,
+    lookup: F,
+) -> tokio::task::JoinHandle<Result<Enqueued, YtdlError>>
+where
+    F: Future<Output = Result<Vec<TrackSympho>, YtdlError>> + Send + 'static,
+{
+    let ticket = next_ticket();
+    let (cancel, cancelled) = oneshot::channel::<()>();
+
+    {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, key);
+        if at_head {
+            sympho_data
+                .queue
+                .insert(0, placeholder(&url, ticket, requester));
+        } else {
+            sympho_data.queue.push(placeholder(&url, ticket, requester));
+        }
+        sympho_data.resolving.insert(ticket, cancel);
+    }
+
+    tokio::spawn(async move {
+        let tracks = tokio::select! {
+            tracks = lookup => tracks,
+            _ = cancelled => Ok(vec![]),
+        };
+
+        match tracks {
+            Ok(tracks) => {
+                Ok(fill_placeholder(&sympho_global_mutex, key, ticket, at_head, tracks).await)
+            }
+            Err(why) => {
+                fill_placeholder(&sympho_global_mutex, key, ticket, at_head, vec![]).await;
+                Err(why)
+            }
+        }
+    })
+}
+
+// Result of a resolution, the tracks over the queue limits are not added
+#[derive(Debug, Default)]
+pub struct Enqueued {
+    pub added: usize,
+    pub rejected: Vec<(TrackSympho, Rejection)>,
+}
+
+// Replace the placeholder of `ticket` with the resolved tracks the queue limits accept.
+// if the placeholder was removed meanwhile(e.g. stop, skip), the tracks are dropped.
+// with fair share, the tracks go to their fair-share position unless they were put `at_head`
+pub async fn fill_placeholder(
+    sympho_global_mutex: &SymphoMap,
+    key: u64,
+    ticket: u64,
+    at_head: bool,
+    mut tracks: Vec<TrackSympho>,
+) -> Enqueued {
+    let mut sympho_global = sympho_global_mutex.write().await;
+    let sympho_data = guild_entry(&mut sympho_global, key);
+
+    sympho_data.resolving.remove(&ticket);
+
+    let index = if let Some(index) = sympho_data
+        .queue
+        .iter()
+        .position(|track| track.pending == Some(ticket))
+    {
+        index
+    } else {
+        return Enqueued::default();
+    };
+
+    // the resolved songs belong to whoever requested the placeholder
+    let requester = sympho_data.queue.remove(index).requester;
+    for track in tracks.iter_mut() {
+        track.requester = requester;
+    }
+
+    let (tracks, rejected) = sympho_data.admit_tracks(tracks);
+
+    let added = tracks.len();
+    sympho_data.queue_duration += tracks.iter().map(|track| track.duration).sum::<Duration>();
+    if sympho_data.fair_share && !at_head {
+        sympho_data.insert_fair_share(tracks);
+    } else {
+        sympho_data.queue.splice(index..index, tracks);
+    }
+
+    Enqueued { added, rejected }
+}
+
+// Metadata lookup, ffprobe runs on the blocking worker pool and youtube-dl through `run_async`
+pub async fn lookup_tracks(
+    url: String,
+    enable_shuffle: bool,
+) -> Result<Vec<TrackSympho>, YtdlError> {
+    if is_file_url(&url) {
+        let probe_url = url.clone();
+        let info = tokio::task::spawn_blocking(move || get_audio_file_info(&probe_url)).await;
+        let (title, duration) = if let Ok(Ok(info)) = info {
+            info
+        } else {
+            ("Unknown".to_string(), Duration::new(0, 0))
+        };
+
+        return Ok(vec![TrackSympho {
+            url,
+            title,
+            duration,
+            ..Default::default()
+        }]);
+    }
+
+    let query_url = url.clone();
+    let output = new_ytdl(&url)
+        .flat_playlist(true)
+        .socket_timeout(5)
+        .process_timeout(Duration::from_secs(YTDL_TIMEOUT_SECS))
+        .run_async()
+        .await;
+
+    match output? {
+        YoutubeDlOutput::Playlist(yt_pl) => {
+            let mut track_vec = playlist_tracks(*yt_pl);
+
+            if enable_shuffle {
+                let mut rng = rand::thread_rng();
+                track_vec.shuffle(&mut rng);
+            }
+
+            Ok(track_vec)
+        }
+        YoutubeDlOutput::SingleVideo(yt_sv) => {
+            let url = yt_sv.webpage_url.unwrap_or("".to_string());
+            if url == "" {
+                return Ok(vec![]);
+            }
+
+            let dur = if let Some(dur) = yt_sv.duration {
+                Duration::from_secs_f64(dur.as_f64().unwrap_or(0.0))
+            } else {
+                Duration::new(0, 0)
+            };
+
+            // webpage_url drops the timestamp, so take it from the url the user gave
+            let start = parse_url_timestamp(&query_url)
+                .filter(|start| *start < dur)
+                .unwrap_or_default();
+
+            Ok(vec![TrackSympho {
+                url,
+                title: yt_sv.title,
+                thumb: yt_sv.thumbnail,
+                duration: dur,
+                start,
+                is_live: yt_sv.is_live.unwrap_or(false),
+                ..Default::default()
+            }])
+        }
+    }
+}
+
+// Tracks of a flat playlist, entries only have the video id as url
+pub fn playlist_tracks(yt_pl: Playlist) -> Vec<TrackSympho> {
+    let entries = yt_pl.entries.unwrap_or(vec![]);
+    let mut track_vec = Vec::new();
+
+    for sv in entries {
+        let url = sv.url.unwrap_or("".to_string());
+        if url != "" {
+            let dur = if let Some(dur) = sv.duration {
+                Duration::from_secs_f64(dur.as_f64().unwrap_or(0.0))
+            } else {
+                Duration::new(0, 0)
+            };
+            track_vec.push(TrackSympho {
+                url: format!("https://www.youtube.com/watch?v={}", url),
+                title: sv.title,
+                thumb: sv.thumbnail,
+                duration: dur,
+                ..Default::default()
+            });
+        };
+    }
+
+    track_vec
+}
+
+// Video id of youtube.com/watch?v=... or youtu.be/... urls
+pub fn youtube_video_id(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    match url.host_str()? {
+        "youtu.be" => url
+            .path_segments()?
+            .next()
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string()),
+        host if host == "youtube.com" || host.ends_with(".youtube.com") => url
+            .query_pairs()
+            .find(|(key, _)| key == "v")
+            .map(|(_, id)| id.to_string()),
+        _ => None,
+    }
+}
+
+// Autoplay lookup, a song related to `seed` that is not in `recent`(urls of the played songs).
+// youtube videos use the mix playlist of the video, the others search youtube by the title.
+pub async fn lookup_related(
+    seed: TrackSympho,
+    recent: Vec<String>,
+) -> Result<Vec<TrackSympho>, YtdlError> {
+    let query = if let Some(id) = youtube_video_id(&seed.url) {
+        format!("https://www.youtube.com/watch?v={}&list=RD{}", id, id)
+    } else {
+        format!("ytsearch{}:{}", AUTOPLAY_CANDIDATES, seed.title)
+    };
+
+    let output = new_ytdl(&query)
+        .flat_playlist(true)
+        .socket_timeout(5)
+        .process_timeout(Duration::from_secs(YTDL_TIMEOUT_SECS))
+        .run_async()
+        .await;
+
+    let candidates = match output? {
+        YoutubeDlOutput::Playlist(yt_pl) => playlist_tracks(*yt_pl),
+        YoutubeDlOutput::SingleVideo(_) => vec![],
+    };
+
+    let played = recent
+        .iter()
+        .chain(std::iter::once(&seed.url))
+        .map(|url| youtube_video_id(url).unwrap_or_else(|| url.clone()))
+        .collect::<HashSet<_>>();
+
+    Ok(candidates
+        .into_iter()
+        .find(|track| {
+            let id = youtube_video_id(&track.url).unwrap_or_else(|| track.url.clone());
+            !played.contains(&id)
+        })
+        .into_iter()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(url: &str, secs: u64) -> TrackSympho {
+        TrackSympho {
+            url: url.to_string(),
+            title: url.to_string(),
+            duration: Duration::from_secs(secs),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn stalled_lookup_does_not_block_other_guilds() {
+        let sympho_global_mutex: SymphoMap = Arc::new(RwLock::new(HashMap::default()));
+        let (release, stalled) = oneshot::channel::<()>();
+
+        let resolution = spawn_resolution(
+            sympho_global_mutex.clone(),
+            1,
+            "https://example.com/stalled".to_string(),
+            None,
+            false,
+            async move {
+                let _ = stalled.await;
+                Ok(vec![
+                    track("https://example.com/a", 3),
+                    track("https://example.com/b", 4),
+                ])
+            },
+        )
+        .await;
+
+        {
+            let sympho_global = sympho_global_mutex.read().await;
+            assert_eq!(sympho_global[&1].queue.len(), 1);
+            assert!(sympho_global[&1].queue[0].pending.is_some());
+        }
+
+        let other_guild = tokio::time::timeout(Duration::from_secs(1), async {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = sympho_global.entry(2).or_insert(SymphoData::default());
+            sympho_data.queue.push(track("https://example.com/c", 5));
+            sympho_data.queue.len()
+        })
+        .await;
+        assert_eq!(other_guild.ok(), Some(1));
+
+        release.send(()).unwrap();
+        assert_eq!(resolution.await.unwrap().ok().map(|e| e.added), Some(2));
+
+        let sympho_global = sympho_global_mutex.read().await;
+        let sympho_data = &sympho_global[&1];
+        assert_eq!(sympho_data.queue.len(), 2);
+        assert!(sympho_data
+            .queue
+            .iter()
+            .all(|track| track.pending.is_none()));
+        assert_eq!(sympho_data.queue_duration, Duration::from_secs(7));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn removed_placeholder_drops_resolved_tracks() {
+        let sympho_global_mutex: SymphoMap = Arc::new(RwLock::new(HashMap::default()));
+        let (release, stalled) = oneshot::channel::<()>();
+
+        let resolution = spawn_resolution(
+            sympho_global_mutex.clone(),
+            1,
+            "https://example.com/stalled".to_string(),
+            None,
+            false,
+            async move {
+                let _ = stalled.await;
+                Ok(vec![track("https://example.com/a", 3)])
+            },
+        )
+        .await;
+
+        sympho_global_mutex
+            .write()
+            .await
+            .get_mut(&1)
+            .unwrap()
+            .queue
+            .clear();
+
+        release.send(()).unwrap();
+        assert_eq!(resolution.await.unwrap().ok().map(|e| e.added), Some(0));
+        assert!(sympho_global_mutex.read().await[&1].queue.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn dropped_sender_cancels_lookup() {
+        let sympho_global_mutex: SymphoMap = Arc::new(RwLock::new(HashMap::default()));
+
+        let resolution = spawn_resolution(
+            sympho_global_mutex.clone(),
+            1,
+            "https://example.com/stalled".to_string(),
+            None,
+            false,
+            async move {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(vec![track("https://example.com/a", 3)])
+            },
+        )
+        .await;
+
+        sympho_global_mutex
+            .write()
+            .await
+            .get_mut(&1)
+            .unwrap()
+            .resolving
+            .clear();
+
+        let resolved = tokio::time::timeout(Duration::from_secs(1), resolution).await;
+        assert_eq!(
+            resolved
+                .ok()
+                .and_then(|res| res.ok())
+                .and_then(|res| res.ok())
+                .map(|e| e.added),
+            Some(0)
+        );
+        assert!(sympho_global_mutex.read().await[&1].queue.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn failed_lookup_removes_placeholder() {
+        let sympho_global_mutex: SymphoMap = Arc::new(RwLock::new(HashMap::default()));
+
+        let resolution = spawn_resolution(
+            sympho_global_mutex.clone(),
+            1,
+            "https://example.com/private".to_string(),
+            None,
+            false,
+            async move { Err(YtdlError::classify(None, "ERROR: Private video")) },
+        )
+        .await;
+
+        let why = resolution.await.unwrap().unwrap_err();
+        assert!(matches!(why, YtdlError::Unavailable(_)));
+        assert!(sympho_global_mutex.read().await[&1].queue.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn at_head_resolution_goes_first() {
+        let sympho_global_mutex: SymphoMap = Arc::new(RwLock::new(HashMap::default()));
+        sympho_global_mutex
+            .write()
+            .await
+            .entry(1)
+            .or_insert(SymphoData::default())
+            .queue
+            .push(track("https://example.com/a", 3));
+
+        let resolution = spawn_resolution(
+            sympho_global_mutex.clone(),
+            1,
+            "https://example.com/next".to_string(),
+            None,
+            true,
+            async move { Ok(vec![track("https://example.com/b", 4)]) },
+        )
+        .await;
+        assert_eq!(resolution.await.unwrap().ok().map(|e| e.added), Some(1));
+
+        let sympho_global = sympho_global_mutex.read().await;
+        let urls = sympho_global[&1]
+            .queue
+            .iter()
+            .map(|track| track.url.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(urls, vec!["https://example.com/b", "https://example.com/a"]);
+    }
+
+    #[test]
+    fn youtube_video_ids() {
+        assert_eq!(
+            youtube_video_id("https://www.youtube.com/watch?v=abc&t=30").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            youtube_video_id("https://youtu.be/abc?t=30").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            youtube_video_id("https://music.youtube.com/watch?v=abc").as_deref(),
+            Some("abc")
+        );
+        assert_eq!(youtube_video_id("https://example.com/watch?v=abc"), None);
+        assert_eq!(youtube_video_id("/tmp/song.mp3"), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn limits_reject_tracks_on_fill() {
+        let sympho_global_mutex: SymphoMap = Arc::new(RwLock::new(HashMap::default()));
+        sympho_global_mutex
+            .write()
+            .await
+            .entry(1)
+            .or_insert(SymphoData::default())
+            .settings
+            .limits
+            .max_queue_len = Some(2);
+
+        let resolution = spawn_resolution(
+            sympho_global_mutex.clone(),
+            1,
+            "https://example.com/playlist".to_string(),
+            Some(UserId(1)),
+            false,
+            async move {
+                Ok(vec![
+                    track("https://example.com/a", 3),
+                    track("https://example.com/b", 4),
+                    track("https://example.com/c", 5),
+                ])
+            },
+        )
+        .await;
+
+        let enqueued = resolution.await.unwrap().unwrap();
+        assert_eq!(enqueued.added, 2);
+        assert_eq!(enqueued.rejected.len(), 1);
+        assert_eq!(enqueued.rejected[0].1, Rejection::QueueFull);
+
+        let sympho_global = sympho_global_mutex.read().await;
+        assert_eq!(sympho_global[&1].queue.len(), 2);
+        assert_eq!(sympho_global[&1].queue_duration, Duration::from_secs(7));
+        assert!(sympho_global[&1]
+            .queue
+            .iter()
+            .all(|track| track.requester == Some(UserId(1))));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::define::*;
+use crate::import::*;
+use crate::permission::*;
+
+// Volume of a guild that never changed it
+pub const DEFAULT_VOLUME: f32 = 1.0;
+
+// Per-guild settings, kept in `SymphoData` and saved to `settings_path()` whenever they change
+// `prefix` None means the default prefix(`SYMPHO_PREFIX`)
+// `bound_channel` is the only text channel that accepts the commands, if set
+// `announce` posts each song when it starts(off on default), `announce_cleanup` deletes the previous post
+// `alone_timeout` and `idle_timeout` are how long the bot stays with no listeners or no songs, None(default) stays forever
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub prefix: Option<String>,
+    pub volume: f32,
+    pub bound_channel: Option<u64>,
+    pub voteskip_ratio: f32,
+    pub permissions: Permissions,
+    pub limits: QueueLimits,
+    pub announce: bool,
+    pub announce_cleanup: bool,
+    pub alone_timeout: Option<Duration>,
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            volume: DEFAULT_VOLUME,
+            bound_channel: None,
+            voteskip_ratio: DEFAULT_VOTESKIP_RATIO,
+            permissions: Permissions::default(),
+            limits: QueueLimits::default(),
+            announce: false,
+            announce_cleanup: false,
+            alone_timeout: None,
+            idle_timeout: None,
+        }
+    }
+}
+
+// `SYMPHO_DATA_DIR`, where the json files are written, the working directory on default
+pub fn data_dir() -> PathBuf {
+    env::var("SYMPHO_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."))
+}
+
+// The path in `env_key`, or `file_name` in `data_dir()`
+pub fn data_path(env_key: &str, file_name: &str) -> PathBuf {
+    env::var(env_key)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| data_dir().join(file_name))
+}
+
+// `SYMPHO_SETTINGS_PATH`, or `sympho_settings.json` in the data dir
+pub fn settings_path() -> PathBuf {
+    data_path("SYMPHO_SETTINGS_PATH", "sympho_settings.json")
+}
+
+// Read a json file written by `write_json`, the default if there is no file yet or it's broken
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let file = match fs::read(path) {
+        Ok(file) => file,
+        Err(why) if why.kind() == std::io::ErrorKind::NotFound => return T::default(),
+        Err(why) => {
+            println!("Couldn't read {}: {:?}", path.display(), why);
+            return T::default();
+        }
+    };
+
+    serde_json::from_slice(&file).unwrap_or_else(|why| {
+        println!("Couldn't parse {}: {:?}", path.display(), why);
+        T::default()
+    })
+}
+
+// Write `value` to the json file.
+// the file is replaced at once, so a crash while writing never leaves a broken file
+pub fn write_json<T: Serialize>(path: &Path, value: &T) {
+    let tmp_path = path.with_extension("json.tmp");
+    let saved = serde_json::to_vec_pretty(value)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            // the data dir may not exist yet
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            Ok(json)
+        })
+        .and_then(|json| Ok(fs::write(&tmp_path, json)?))
+        .and_then(|_| Ok(fs::rename(&tmp_path, path)?));
+
+    if let Err(why) = saved {
+        println!("Couldn't save {}: {:?}", path.display(), why);
+    }
+}
+
+// `write_json` on the blocking pool, the other guilds don't wait for the disk
+pub async fn write_json_blocking<T: Serialize + Send + 'static>(path: PathBuf, value: T) {
+    if let Err(why) = tokio::task::spawn_blocking(move || write_json(&path, &value)).await {
+        println!("Couldn't save the json file: {:?}", why);
+    }
+}
+
+// Settings saved by `save_settings`, empty if there is no file yet
+pub fn load_settings() -> HashMap<u64, GuildSettings> {
+    read_json(&settings_path())
+}
+
+// Global data with the saved settings of each guild
+pub fn load_sympho_map() -> SymphoMap {
+    let sympho_global = load_settings()
+        .into_iter()
+        .map(|(key, settings)| (key, SymphoData::for_guild(settings)))
+        .collect::<HashMap<_, _>>();
+
+    Arc::new(RwLock::new(sympho_global))
+}
+
+// Only one write of the settings at a time, an older copy must never overwrite a newer one
+static SETTINGS_WRITE: OnceCell<tokio::sync::Mutex<()>> = OnceCell::new();
+
+// Write the settings of all guilds, only the guilds that changed something are written.
+// call it after the write lock is released, they are copied under a short read lock
+pub async fn save_settings(sympho_global_mutex: &SymphoMap) {
+    let _write = SETTINGS_WRITE.get_or_init(Default::default).lock().await;
+    let settings = sympho_global_mutex
+        .read()
+        .await
+        .iter()
+        .filter(|(_, sympho_data)| sympho_data.settings != GuildSettings::default())
+        .map(|(key, sympho_data)| (*key, sympho_data.settings.clone()))
+        .collect::<HashMap<_, _>>();
+
+    write_json_blocking(settings_path(), settings).await;
+}
+
+// Text channel bound to the guild, if any
+pub async fn bound_channel_of(ctx: &Context, key: u64) -> Option<ChannelId> {
+    let data = ctx.data.read().await;
+    let sympho_global_mutex = data.get::<SymphoGlobal>()?;
+    let sympho_global = sympho_global_mutex.read().await;
+
+    sympho_global
+        .get(&key)
+        .and_then(|sympho_data| sympho_data.settings.bound_channel)
+        .map(ChannelId)
+}
+
+// Prefix given by `SYMPHO_PREFIX`
+pub fn default_prefix() -> String {
+    if let Ok(sympho_prefix) = SYMPHO_PREFIX
+        .get_or_init(|| Mutex::new("!".to_string()))
+        .lock()
+    {
+        (*sympho_prefix).clone()
+    } else {
+        "!".to_string()
+    }
+}
+
+// Prefix of the guild, the default prefix outside of guilds
+pub async fn prefix_of(ctx: &Context, key: Option<u64>) -> String {
+    if let Some(key) = key {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            if let Some(sympho_data) = sympho_global_mutex.read().await.get(&key) {
+                if let Some(prefix) = &sympho_data.settings.prefix {
+                    return prefix.clone();
+                }
+            }
+        }
+    }
+
+    default_prefix()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = GuildSettings {
+            prefix: Some("?".to_string()),
+            volume: 0.5,
+            bound_channel: Some(42),
+            ..Default::default()
+        };
+        settings
+            .permissions
+            .command_levels
+            .insert("play".to_string(), PermissionLevel::Everyone);
+        settings.limits.max_duration = Some(Duration::from_secs(600));
+
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(
+            serde_json::from_str::<GuildSettings>(&json).unwrap(),
+            settings
+        );
+    }
+
+    #[test]
+    fn missing_fields_are_default() {
+        let settings = serde_json::from_str::<GuildSettings>(r#"{"prefix": "?"}"#).unwrap();
+        assert_eq!(settings.prefix.as_deref(), Some("?"));
+        assert_eq!(settings.volume, DEFAULT_VOLUME);
+        assert_eq!(settings.limits, QueueLimits::default());
+        assert!(!settings.announce);
+        assert_eq!(settings.idle_timeout, None);
+    }
+}
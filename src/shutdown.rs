@@ -0,0 +1,109 @@
+use crate::define::*;
+use crate::import::*;
+use crate::playlist::*;
+use crate::settings::*;
+use crate::snapshot::*;
+
+// How long the voice cleanup can take before the shards are shut down anyway
+pub const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(10);
+
+// Set once the shutdown started, nothing new is played after that
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+// Wait for SIGINT(Ctrl+C), or SIGTERM on unix
+pub async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+// Stop the songs and leave the voice channels of all guilds.
+// the snapshot is taken first, so the songs can be resumed from where they were stopped
+async fn leave_all(data: &Arc<serenity::prelude::RwLock<TypeMap>>) {
+    save_snapshot(data).await;
+
+    let (sympho_global_mutex, manager) = {
+        let data = data.read().await;
+        (
+            data.get::<SymphoGlobal>().cloned(),
+            data.get::<songbird::SongbirdKey>().cloned(),
+        )
+    };
+
+    let keys = if let Some(sympho_global_mutex) = &sympho_global_mutex {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        for sympho_data in sympho_global.values_mut() {
+            sympho_data.starting = None;
+            // cancel the lookups, no one will play them
+            sympho_data.resolving.clear();
+            if let Some((current, _)) = sympho_data.current.take() {
+                let _ = current.stop();
+            }
+        }
+
+        sympho_global.keys().copied().collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+
+    if let Some(sympho_global_mutex) = &sympho_global_mutex {
+        save_settings(sympho_global_mutex).await;
+    }
+    if let Some(playlist_map) = data.read().await.get::<SymphoPlaylists>().cloned() {
+        save_all_playlists(&playlist_map).await;
+    }
+
+    if let Some(manager) = manager {
+        for key in keys {
+            if manager.get(GuildId(key)).is_some() {
+                if let Err(why) = manager.remove(GuildId(key)).await {
+                    println!("Couldn't leave the guild {}: {:?}", key, why);
+                }
+            }
+        }
+    }
+}
+
+// Clean up the voice connections and the state, then shut down the shards.
+// a stuck cleanup never keeps the process alive longer than `SHUTDOWN_DEADLINE`
+pub async fn shutdown(
+    data: &Arc<serenity::prelude::RwLock<TypeMap>>,
+    shard_manager: &Arc<serenity::prelude::Mutex<ShardManager>>,
+) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    println!("Shutting down...");
+
+    if tokio::time::timeout(SHUTDOWN_DEADLINE, leave_all(data))
+        .await
+        .is_err()
+    {
+        println!("Voice cleanup took too long, shutting down anyway");
+    }
+
+    if tokio::time::timeout(SHUTDOWN_DEADLINE, async {
+        shard_manager.lock().await.shutdown_all().await;
+    })
+    .await
+    .is_err()
+    {
+        println!("Shards didn't shut down in time");
+        std::process::exit(1);
+    }
+}
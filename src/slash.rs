@@ -0,0 +1,708 @@
+use crate::commands::queue::*;
+use crate::define::*;
+use crate::hooks::*;
+use crate::import::*;
+use crate::panel::*;
+use crate::permission::*;
+use crate::resolver::*;
+
+// How many search results `play` suggests while typing
+pub const AUTOCOMPLETE_LEN: usize = 5;
+// Discord waits 3 secs for the suggestions
+pub const AUTOCOMPLETE_TIMEOUT: Duration = Duration::from_millis(2500);
+
+// Type of a slash command option, and how its value is written as a prefix command argument
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SlashKind {
+    // as is
+    Text,
+    Integer,
+    Number,
+    // `<@id>`
+    User,
+    // `<@&id>`
+    Role,
+    // `on` or `off`
+    Switch,
+    // the name of the option if true(e.g. `shuffle`), nothing if false
+    Flag,
+    // `--` and the name of the option if true(e.g. `--mine`), nothing if false
+    LongFlag,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SlashOption {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: SlashKind,
+    pub required: bool,
+    pub choices: &'static [&'static str],
+    pub autocomplete: bool,
+}
+
+// A subcommand, `arg` is the first argument of the prefix command(None for no argument)
+// and the options are the arguments after it
+#[derive(Clone, Copy, Debug)]
+pub struct SlashSub {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub arg: Option<&'static str>,
+    pub options: &'static [SlashOption],
+}
+
+const fn sub(
+    name: &'static str,
+    description: &'static str,
+    options: &'static [SlashOption],
+) -> SlashSub {
+    SlashSub {
+        name,
+        description,
+        arg: Some(name),
+        options,
+    }
+}
+
+const fn option(name: &'static str, description: &'static str, kind: SlashKind) -> SlashOption {
+    SlashOption {
+        name,
+        description,
+        kind,
+        required: false,
+        choices: &[],
+        autocomplete: false,
+    }
+}
+
+const fn required(option: SlashOption) -> SlashOption {
+    SlashOption {
+        required: true,
+        ..option
+    }
+}
+
+const fn choices(option: SlashOption, choices: &'static [&'static str]) -> SlashOption {
+    SlashOption { choices, ..option }
+}
+
+const fn autocomplete(option: SlashOption) -> SlashOption {
+    SlashOption {
+        autocomplete: true,
+        ..option
+    }
+}
+
+const QUERY: SlashOption = autocomplete(required(option(
+    "query",
+    "url or keywords",
+    SlashKind::Text,
+)));
+const SHUFFLE: SlashOption = option("shuffle", "shuffle the playlist", SlashKind::Flag);
+const MINE: SlashOption = option(
+    "mine",
+    "your own playlists instead of the ones of the server",
+    SlashKind::LongFlag,
+);
+const ENABLED: SlashOption = required(option("enabled", "on or off", SlashKind::Switch));
+const FILTER_USER: SlashOption = option("user", "only the songs of the user", SlashKind::User);
+const FILTER_START: SlashOption = option("start", "first song(1 first)", SlashKind::Integer);
+const FILTER_END: SlashOption = option("end", "last song", SlashKind::Integer);
+const ROLE_ACTION: SlashOption = required(choices(
+    option("action", "add or remove the role", SlashKind::Text),
+    &["add", "remove"],
+));
+const ROLE: SlashOption = required(option("role", "the role", SlashKind::Role));
+const CONFIG_KEY: SlashOption = choices(
+    option("key", "name of the setting", SlashKind::Text),
+    crate::commands::config::CONFIG_KEYS,
+);
+const PLAYLIST_NAME: SlashOption =
+    required(option("name", "name of the playlist", SlashKind::Text));
+
+// Options of each command in the order of the prefix command arguments,
+// and the name of the slash command if it's not the name of the command
+// the commands not here have no options
+pub const SLASH_COMMANDS: &[(&str, Option<&str>, &[SlashOption])] = &[
+    (
+        "help",
+        None,
+        &[option("command", "name of the command", SlashKind::Text)],
+    ),
+    ("play", None, &[QUERY, SHUFFLE]),
+    ("playnext", None, &[QUERY]),
+    (
+        "volume",
+        None,
+        &[required(option("volume", "0.1 ~ 100.0", SlashKind::Number))],
+    ),
+    (
+        "skip",
+        None,
+        &[
+            option(
+                "start",
+                "number of the song on the queue",
+                SlashKind::Integer,
+            ),
+            option("end", "skip until this song", SlashKind::Integer),
+        ],
+    ),
+    (
+        "looping",
+        Some("loop"),
+        &[required(choices(
+            option("mode", "loop mode", SlashKind::Text),
+            &["off", "track", "queue"],
+        ))],
+    ),
+    (
+        "queue",
+        None,
+        &[
+            option("page", "page to show(1 first)", SlashKind::Integer),
+            option(
+                "search",
+                "only the songs with this in the title",
+                SlashKind::Text,
+            ),
+        ],
+    ),
+    (
+        "move_track",
+        Some("move"),
+        &[
+            required(option("from", "number of the song", SlashKind::Integer)),
+            required(option("to", "new position", SlashKind::Integer)),
+        ],
+    ),
+    (
+        "swap",
+        None,
+        &[
+            required(option("first", "number of the song", SlashKind::Integer)),
+            required(option("second", "number of the song", SlashKind::Integer)),
+        ],
+    ),
+    (
+        "remove",
+        None,
+        &[
+            required(option("start", "number of the song", SlashKind::Integer)),
+            option("end", "remove until this song", SlashKind::Integer),
+        ],
+    ),
+    ("shuffle", None, &[FILTER_USER, FILTER_START, FILTER_END]),
+    ("reverse", None, &[FILTER_USER, FILTER_START, FILTER_END]),
+    ("dedupe", None, &[FILTER_USER, FILTER_START, FILTER_END]),
+    (
+        "seek",
+        None,
+        &[required(option(
+            "position",
+            "e.g. 1:30, +30s or -10s",
+            SlashKind::Text,
+        ))],
+    ),
+    ("autoplay", None, &[ENABLED]),
+    ("fairshare", None, &[ENABLED]),
+    (
+        "limits",
+        None,
+        &[
+            choices(
+                option("limit", "limit to set", SlashKind::Text),
+                &["queue", "duration", "user", "live"],
+            ),
+            option("value", "value or off", SlashKind::Text),
+        ],
+    ),
+];
+
+// Subcommands of the commands that take an action first, they have no options of their own
+pub const SLASH_SUBCOMMANDS: &[(&str, &[SlashSub])] = &[
+    (
+        "permission",
+        &[
+            SlashSub {
+                arg: None,
+                ..sub("show", "show the settings", &[])
+            },
+            sub("dj", "edit the dj roles", &[ROLE_ACTION, ROLE]),
+            sub("admin", "edit the admin roles", &[ROLE_ACTION, ROLE]),
+            sub(
+                "command",
+                "change the level of a command",
+                &[
+                    required(option("name", "name of the command", SlashKind::Text)),
+                    required(choices(
+                        option("level", "who can use it", SlashKind::Text),
+                        &["everyone", "dj", "admin", "default"],
+                    )),
+                ],
+            ),
+            sub(
+                "alone",
+                "let everyone use the dj commands when alone with the bot",
+                &[ENABLED],
+            ),
+        ],
+    ),
+    (
+        "config",
+        &[
+            sub("get", "show the settings", &[CONFIG_KEY]),
+            sub(
+                "set",
+                "change a setting",
+                &[
+                    required(CONFIG_KEY),
+                    required(option("value", "new value", SlashKind::Text)),
+                ],
+            ),
+            sub(
+                "reset",
+                "back to the default, all settings without the key",
+                &[CONFIG_KEY],
+            ),
+        ],
+    ),
+    (
+        "playlist",
+        &[
+            sub(
+                "save",
+                "save the current song and the queue",
+                &[PLAYLIST_NAME, MINE],
+            ),
+            sub(
+                "load",
+                "add the songs to the queue",
+                &[PLAYLIST_NAME, SHUFFLE, MINE],
+            ),
+            sub(
+                "show",
+                "show the songs",
+                &[
+                    PLAYLIST_NAME,
+                    option("page", "page to show(1 first)", SlashKind::Integer),
+                    MINE,
+                ],
+            ),
+            sub("delete", "delete the playlist", &[PLAYLIST_NAME, MINE]),
+            sub("add", "add a song", &[PLAYLIST_NAME, QUERY, MINE]),
+            sub(
+                "remove",
+                "remove a song",
+                &[
+                    PLAYLIST_NAME,
+                    required(option("number", "number of the song", SlashKind::Integer)),
+                    MINE,
+                ],
+            ),
+            sub(
+                "list",
+                "show the playlists of the server",
+                &[
+                    option("user", "only the playlists of the user", SlashKind::User),
+                    MINE,
+                ],
+            ),
+        ],
+    ),
+];
+
+// Name of the slash command of `command` and its options
+pub fn slash_spec_of(command: &'static str) -> (&'static str, &'static [SlashOption]) {
+    let name = canonical_command_name(command).unwrap_or(command);
+    SLASH_COMMANDS
+        .iter()
+        .find(|(command, _, _)| *command == name)
+        .map(|(command, slash_name, options)| (slash_name.unwrap_or(*command), *options))
+        .unwrap_or((name, &[]))
+}
+
+// Subcommands of `command`, empty if it has none
+pub fn slash_subcommands_of(command: &str) -> &'static [SlashSub] {
+    let name = canonical_command_name(command).unwrap_or(command);
+    SLASH_SUBCOMMANDS
+        .iter()
+        .find(|(command, _)| *command == name)
+        .map(|(_, subcommands)| *subcommands)
+        .unwrap_or(&[])
+}
+
+// The command of the slash command `slash_name`
+pub fn command_of_slash(slash_name: &str) -> Option<&'static FrameworkCommand> {
+    GENERAL_GROUP
+        .options
+        .commands
+        .iter()
+        .find(|cmd| slash_spec_of(cmd.options.names[0]).0 == slash_name)
+        .copied()
+}
+
+// First line of the description, slash command descriptions are 100 chars at most
+fn short_description(cmd: &FrameworkCommand) -> String {
+    cmd.options
+        .desc
+        .and_then(|desc| desc.lines().next())
+        .unwrap_or("Sympho command")
+        .chars()
+        .take(100)
+        .collect()
+}
+
+// The arguments of the prefix command given by the options, in the order of `options`
+pub fn args_of(options: &[SlashOption], values: &HashMap<String, Value>) -> String {
+    options
+        .iter()
+        .filter_map(|option| {
+            let value = values.get(option.name)?;
+            match option.kind {
+                SlashKind::Text => value.as_str().map(|value| value.to_string()),
+                SlashKind::Integer | SlashKind::Number => Some(value.to_string()),
+                SlashKind::User => value.as_str().map(|id| format!("<@{}>", id)),
+                SlashKind::Role => value.as_str().map(|id| format!("<@&{}>", id)),
+                SlashKind::Switch => value
+                    .as_bool()
+                    .map(|on| if on { "on" } else { "off" }.to_string()),
+                SlashKind::Flag => match value.as_bool() {
+                    Some(true) => Some(option.name.to_string()),
+                    _ => None,
+                },
+                SlashKind::LongFlag => match value.as_bool() {
+                    Some(true) => Some(format!("--{}", option.name)),
+                    _ => None,
+                },
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The arguments of the prefix command given by the subcommand `sub_name` of `command`
+pub fn subcommand_args_of(
+    command: &str,
+    sub_name: &str,
+    values: &HashMap<String, Value>,
+) -> Option<String> {
+    let sub = slash_subcommands_of(command)
+        .iter()
+        .find(|sub| sub.name == sub_name)?;
+
+    Some(
+        sub.arg
+            .map(|arg| arg.to_string())
+            .into_iter()
+            .chain(Some(args_of(sub.options, values)))
+            .filter(|arg| !arg.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+fn create_option<'a>(
+    o: &'a mut CreateApplicationCommandOption,
+    option: &SlashOption,
+) -> &'a mut CreateApplicationCommandOption {
+    o.name(option.name)
+        .description(option.description)
+        .required(option.required)
+        .kind(match option.kind {
+            SlashKind::Text => ApplicationCommandOptionType::String,
+            SlashKind::Integer => ApplicationCommandOptionType::Integer,
+            SlashKind::Number => ApplicationCommandOptionType::Number,
+            SlashKind::User => ApplicationCommandOptionType::User,
+            SlashKind::Role => ApplicationCommandOptionType::Role,
+            SlashKind::Switch | SlashKind::Flag | SlashKind::LongFlag => {
+                ApplicationCommandOptionType::Boolean
+            }
+        });
+    if option.autocomplete {
+        o.set_autocomplete(true);
+    }
+    for choice in option.choices {
+        o.add_string_choice(choice, choice);
+    }
+    o
+}
+
+fn create_commands(commands: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    for cmd in GENERAL_GROUP.options.commands {
+        let (name, options) = slash_spec_of(cmd.options.names[0]);
+        commands.create_application_command(|c| {
+            c.name(name).description(short_description(cmd));
+            for option in options {
+                c.create_option(|o| create_option(o, option));
+            }
+            for subcommand in slash_subcommands_of(cmd.options.names[0]) {
+                c.create_option(|o| {
+                    o.name(subcommand.name)
+                        .description(subcommand.description)
+                        .kind(ApplicationCommandOptionType::SubCommand);
+                    for option in subcommand.options {
+                        o.create_sub_option(|o| create_option(o, option));
+                    }
+                    o
+                });
+            }
+            c
+        });
+    }
+
+    commands
+}
+
+// `SYMPHO_SLASH_COMMANDS=global`(default) registers the commands for all guilds,
+// `guild` for each guild the bot is in(shows up at once, handy while testing), `off` doesn't register them
+pub async fn register_slash_commands(ctx: &Context, ready: &Ready) {
+    match env::var("SYMPHO_SLASH_COMMANDS").as_deref() {
+        Ok("off") => {}
+        Ok("guild") => {
+            for guild in &ready.guilds {
+                if let Err(why) = guild
+                    .id()
+                    .set_application_commands(&ctx.http, create_commands)
+                    .await
+                {
+                    println!(
+                        "Couldn't register the slash commands to {}: {:?}",
+                        guild.id(),
+                        why
+                    );
+                }
+            }
+        }
+        _ => {
+            if let Err(why) =
+                ApplicationCommand::set_global_application_commands(&ctx.http, create_commands)
+                    .await
+            {
+                println!("Couldn't register the slash commands: {:?}", why);
+            }
+        }
+    }
+}
+
+// Run the slash command through the same hooks and command as the prefix command.
+// the reply to the interaction becomes the message the command replies to,
+// it's made to look like the user sent it so the permission checks see the user
+async fn run_slash_command(ctx: &Context, interaction: &ApplicationCommandInteraction) {
+    let cmd = if let Some(cmd) = command_of_slash(&interaction.data.name) {
+        cmd
+    } else {
+        return;
+    };
+    let command_name = cmd.options.names[0];
+    let (slash_name, options) = slash_spec_of(command_name);
+
+    let values_of = |options: &[ApplicationCommandInteractionDataOption]| {
+        options
+            .iter()
+            .filter_map(|option| Some((option.name.clone(), option.value.clone()?)))
+            .collect::<HashMap<_, _>>()
+    };
+    // a subcommand is the only option when the command has them
+    let args = match interaction.data.options.first() {
+        Some(option) if option.kind == ApplicationCommandOptionType::SubCommand => {
+            subcommand_args_of(command_name, &option.name, &values_of(&option.options))
+                .unwrap_or_default()
+        }
+        _ => args_of(options, &values_of(&interaction.data.options)),
+    };
+
+    let echo = if interaction.guild_id.is_none() {
+        "This command can only be used in a server.".to_string()
+    } else if args.is_empty() {
+        format!("{} used `/{}`", interaction.user.mention(), slash_name)
+    } else {
+        format!(
+            "{} used `/{} {}`",
+            interaction.user.mention(),
+            slash_name,
+            args
+        )
+    };
+    if let Err(why) = interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(echo))
+        })
+        .await
+    {
+        println!("Couldn't respond to /{}: {:?}", slash_name, why);
+        return;
+    }
+    if interaction.guild_id.is_none() {
+        return;
+    }
+
+    let msg = match interaction.get_interaction_response(&ctx.http).await {
+        Ok(msg) => msg,
+        Err(why) => {
+            println!("Couldn't get the response of /{}: {:?}", slash_name, why);
+            return;
+        }
+    };
+    let msg = message_as_user(
+        ctx,
+        msg,
+        &interaction.user,
+        interaction.member.as_ref(),
+        interaction.guild_id,
+        command_name,
+        &args,
+    )
+    .await;
+
+    run_command(ctx, &msg, cmd, &args).await;
+}
+
+// Search results of the keywords being typed into the `query` of `play`
+async fn suggest_songs(ctx: &Context, interaction: &AutocompleteInteraction) {
+    // `playlist add` has it in the subcommand
+    let query = interaction
+        .data
+        .options
+        .iter()
+        .chain(
+            interaction
+                .data
+                .options
+                .iter()
+                .flat_map(|option| option.options.iter()),
+        )
+        .find(|option| option.focused)
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    // urls are played as they are
+    let tracks = if query.chars().count() < 3 || Url::parse(&query).is_ok() {
+        vec![]
+    } else {
+        // always the subprocess backend, its child is killed on timeout,
+        // while a timed out pyo3 lookup keeps the GIL the `play` lookups need
+        let output = new_ytdl(&format!("ytsearch{}:{}", AUTOCOMPLETE_LEN, query))
+            .backend_kind(BackendKind::Subprocess)
+            .flat_playlist(true)
+            .socket_timeout(2)
+            .process_timeout(AUTOCOMPLETE_TIMEOUT)
+            .run_async()
+            .await;
+
+        match output {
+            Ok(YoutubeDlOutput::Playlist(yt_pl)) => playlist_tracks(*yt_pl),
+            _ => vec![],
+        }
+    };
+
+    let _ = interaction
+        .create_autocomplete_response(&ctx.http, |r| {
+            for track in tracks.iter().filter(|track| track.url.len() <= 100) {
+                r.add_string_choice(
+                    track.title.chars().take(100).collect::<String>(),
+                    &track.url,
+                );
+            }
+            r
+        })
+        .await;
+}
+
+// Entry point of the interactions, called by `Handler`
+pub async fn handle_interaction(ctx: &Context, interaction: Interaction) {
+    match interaction {
+        Interaction::ApplicationCommand(interaction) => run_slash_command(ctx, &interaction).await,
+        Interaction::Autocomplete(interaction) => suggest_songs(ctx, &interaction).await,
+        Interaction::MessageComponent(interaction)
+            if interaction.data.custom_id.starts_with(QUEUE_BUTTON_PREFIX) =>
+        {
+            press_queue_button(ctx, &interaction).await
+        }
+        Interaction::MessageComponent(interaction) => press_button(ctx, &interaction).await,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_become_args() {
+        let (_, options) = slash_spec_of("play");
+        let mut values = HashMap::new();
+        values.insert("query".to_string(), json!("never gonna"));
+        assert_eq!(args_of(options, &values), "never gonna");
+
+        values.insert("shuffle".to_string(), json!(true));
+        assert_eq!(args_of(options, &values), "never gonna shuffle");
+
+        let (_, options) = slash_spec_of("shuffle");
+        let mut values = HashMap::new();
+        values.insert("user".to_string(), json!("42"));
+        values.insert("end".to_string(), json!(5));
+        values.insert("start".to_string(), json!(2));
+        assert_eq!(args_of(options, &values), "<@42> 2 5");
+    }
+
+    #[test]
+    fn subcommands_become_args() {
+        let mut values = HashMap::new();
+        values.insert("action".to_string(), json!("add"));
+        values.insert("role".to_string(), json!("42"));
+        assert_eq!(
+            subcommand_args_of("permission", "dj", &values).as_deref(),
+            Some("dj add <@&42>")
+        );
+        assert_eq!(
+            subcommand_args_of("perm", "show", &HashMap::new()).as_deref(),
+            Some("")
+        );
+
+        let mut values = HashMap::new();
+        values.insert("shuffle".to_string(), json!(true));
+        values.insert("name".to_string(), json!("chill"));
+        assert_eq!(
+            subcommand_args_of("playlist", "load", &values).as_deref(),
+            Some("load chill shuffle")
+        );
+        values.insert("mine".to_string(), json!(true));
+        assert_eq!(
+            subcommand_args_of("playlist", "load", &values).as_deref(),
+            Some("load chill shuffle --mine")
+        );
+        assert_eq!(subcommand_args_of("playlist", "nope", &values), None);
+
+        // discord doesn't allow the options next to the subcommands
+        for (command, _) in SLASH_SUBCOMMANDS {
+            assert!(command_named(command).is_some());
+            assert!(slash_spec_of(command).1.is_empty());
+        }
+    }
+
+    #[test]
+    fn slash_names() {
+        assert_eq!(slash_spec_of("looping").0, "loop");
+        assert_eq!(slash_spec_of("mv").0, "move");
+        assert_eq!(
+            command_of_slash("loop").map(|cmd| cmd.options.names[0]),
+            Some("looping")
+        );
+        assert_eq!(
+            command_of_slash("stop").map(|cmd| cmd.options.names[0]),
+            Some("stop")
+        );
+
+        // discord rejects duplicated or too long names
+        let mut names = HashSet::new();
+        for cmd in GENERAL_GROUP.options.commands {
+            let name = slash_spec_of(cmd.options.names[0]).0;
+            assert!(name.len() <= 32 && name == name.to_lowercase());
+            assert!(names.insert(name));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use crate::define::*;
+use crate::import::*;
+use crate::player::*;
+use crate::settings::*;
+use crate::shutdown::*;
+use crate::watchdog::*;
+
+// How often the snapshots are written if `SYMPHO_SNAPSHOT_INTERVAL`(secs) is not set
+pub const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+
+// `ready` is raised again on reconnect, the snapshot task is started only once
+static SNAPSHOT_STARTED: AtomicBool = AtomicBool::new(false);
+// Held while a snapshot is taken and written, so a periodic one never overwrites the last one of the shutdown
+static SNAPSHOT_WRITE: OnceCell<tokio::sync::Mutex<()>> = OnceCell::new();
+
+// Playback state of a guild, enough to continue where it left off
+// `current.start` is the position the current song was at
+// the queue doesn't have the placeholders, their lookup is gone with the process
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSnapshot {
+    pub current: Option<TrackSympho>,
+    pub queue: Vec<TrackSympho>,
+    pub volume: f32,
+    pub loop_mode: LoopMode,
+    pub autoplay: bool,
+    pub fair_share: bool,
+    pub voice_channel: Option<u64>,
+    pub text_channel: Option<u64>,
+}
+
+// `SYMPHO_SNAPSHOT_PATH`, or `sympho_snapshot.json` in the data dir
+pub fn snapshot_path() -> PathBuf {
+    data_path("SYMPHO_SNAPSHOT_PATH", "sympho_snapshot.json")
+}
+
+pub fn snapshot_interval() -> Duration {
+    env::var("SYMPHO_SNAPSHOT_INTERVAL")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SNAPSHOT_INTERVAL)
+}
+
+// `SYMPHO_RESUME=on` resumes the saved snapshots on startup
+pub fn resume_enabled() -> bool {
+    matches!(
+        env::var("SYMPHO_RESUME").as_deref(),
+        Ok("on") | Ok("true") | Ok("1")
+    )
+}
+
+// Snapshot of the guild, None if there is nothing to resume
+// `position` is where the current song is at, `voice_channel` is where the bot is
+pub fn snapshot_of(
+    sympho_data: &SymphoData,
+    position: Option<Duration>,
+    voice_channel: Option<ChannelId>,
+) -> Option<GuildSnapshot> {
+    let current = sympho_data.current.as_ref().map(|(_, track)| {
+        let mut track = track.clone();
+        // live streams can't be seeked, they start from now anyway
+        track.start = if track.is_live {
+            Duration::default()
+        } else {
+            position.unwrap_or(track.start)
+        };
+        track
+    });
+    let queue = sympho_data
+        .queue
+        .iter()
+        .filter(|track| track.pending.is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if current.is_none() && queue.is_empty() && voice_channel.is_none() {
+        return None;
+    }
+
+    Some(GuildSnapshot {
+        current,
+        queue,
+        volume: sympho_data.volume,
+        loop_mode: sympho_data.loop_mode,
+        autoplay: sympho_data.autoplay,
+        fair_share: sympho_data.fair_share,
+        voice_channel: voice_channel.map(|channel_id| channel_id.0),
+        text_channel: sympho_data.text_channel.map(|channel_id| channel_id.0),
+    })
+}
+
+// Put the snapshot back to the guild, the current song goes to the head of the queue
+// so the playback driver starts it from the saved position
+pub fn restore_snapshot(sympho_data: &mut SymphoData, snapshot: GuildSnapshot) {
+    sympho_data.clear_queue();
+    sympho_data.queue = snapshot
+        .current
+        .into_iter()
+        .chain(snapshot.queue.into_iter())
+        .collect();
+    sympho_data.queue_duration = sympho_data.queue.iter().map(|track| track.duration).sum();
+    sympho_data.volume = snapshot.volume;
+    sympho_data.loop_mode = snapshot.loop_mode;
+    sympho_data.autoplay = snapshot.autoplay;
+    sympho_data.fair_share = snapshot.fair_share;
+    sympho_data.text_channel = snapshot.text_channel.map(ChannelId);
+}
+
+// Snapshots of all guilds that have something to resume
+pub async fn take_snapshots(
+    data: &Arc<serenity::prelude::RwLock<TypeMap>>,
+) -> HashMap<u64, GuildSnapshot> {
+    let (sympho_global_mutex, manager) = {
+        let data = data.read().await;
+        (
+            data.get::<SymphoGlobal>().cloned(),
+            data.get::<songbird::SongbirdKey>().cloned(),
+        )
+    };
+    let sympho_global_mutex = if let Some(sympho_global_mutex) = sympho_global_mutex {
+        sympho_global_mutex
+    } else {
+        return HashMap::new();
+    };
+
+    // asking the driver for the position takes a while, so don't hold the lock meanwhile
+    let currents = sympho_global_mutex
+        .read()
+        .await
+        .iter()
+        .map(|(key, sympho_data)| {
+            (
+                *key,
+                sympho_data
+                    .current
+                    .as_ref()
+                    .map(|(track_handle, _)| track_handle.clone()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut snapshots = HashMap::new();
+    for (key, track_handle) in currents {
+        let position = if let Some(track_handle) = track_handle {
+            track_handle
+                .get_info()
+                .await
+                .ok()
+                .map(|track_state| track_state.position)
+        } else {
+            None
+        };
+
+        let voice_channel = if let Some(handler_lock) = manager
+            .as_ref()
+            .and_then(|manager| manager.get(GuildId(key)))
+        {
+            handler_lock
+                .lock()
+                .await
+                .current_channel()
+                .map(|channel_id| ChannelId(channel_id.0))
+        } else {
+            None
+        };
+
+        if let Some(snapshot) = sympho_global_mutex
+            .read()
+            .await
+            .get(&key)
+            .and_then(|sympho_data| snapshot_of(sympho_data, position, voice_channel))
+        {
+            snapshots.insert(key, snapshot);
+        }
+    }
+
+    snapshots
+}
+
+async fn write_snapshot(data: &Arc<serenity::prelude::RwLock<TypeMap>>) {
+    let snapshots = take_snapshots(data).await;
+    write_json_blocking(snapshot_path(), snapshots).await;
+}
+
+// Write the snapshots of all guilds to `snapshot_path()`, the shutdown writes the last one with it
+pub async fn save_snapshot(data: &Arc<serenity::prelude::RwLock<TypeMap>>) {
+    let _write = SNAPSHOT_WRITE.get_or_init(Default::default).lock().await;
+    write_snapshot(data).await;
+}
+
+// Restore the saved snapshots, rejoin the voice channels and continue the songs
+pub async fn resume_snapshot(ctx: &Context) {
+    let snapshots: HashMap<u64, GuildSnapshot> = read_json(&snapshot_path());
+
+    let sympho_global_mutex = {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            sympho_global_mutex.clone()
+        } else {
+            return;
+        }
+    };
+
+    for (key, snapshot) in snapshots {
+        let channels = snapshot.voice_channel.zip(snapshot.text_channel).map(
+            |(voice_channel, text_channel)| (ChannelId(voice_channel), ChannelId(text_channel)),
+        );
+
+        {
+            let mut sympho_global = sympho_global_mutex.write().await;
+            let sympho_data = guild_entry(&mut sympho_global, key);
+            restore_snapshot(sympho_data, snapshot);
+        }
+
+        // the queue is kept even if the bot wasn't in a voice channel
+        let (voice_channel, chan_id) = if let Some(channels) = channels {
+            channels
+        } else {
+            continue;
+        };
+
+        match connect(ctx, GuildId(key), voice_channel, chan_id).await {
+            Ok(handler_lock) => {
+                let dropped = start_next(&handler_lock, &sympho_global_mutex, key).await;
+                say_dropped_tracks(&ctx.http, chan_id, &dropped).await;
+                if !dropped.is_empty() {
+                    watch_idle(&ctx.data, &ctx.http, key).await;
+                }
+            }
+            Err(why) => println!("Couldn't resume the guild {}: {}", key, why),
+        }
+    }
+}
+
+// Resume the snapshots if enabled, then keep writing them periodically
+pub async fn start_snapshots(ctx: Context) {
+    if SNAPSHOT_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    if resume_enabled() {
+        resume_snapshot(&ctx).await;
+    }
+
+    let interval = snapshot_interval();
+    tokio::spawn(async move {
+        loop {
+            sleep(interval).await;
+
+            let _write = SNAPSHOT_WRITE.get_or_init(Default::default).lock().await;
+            // checked under the lock, the shutdown may have written the last one meanwhile.
+            // the voice channels are gone after it
+            if is_shutting_down() {
+                break;
+            }
+            write_snapshot(&ctx.data).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(url: &str) -> TrackSympho {
+        TrackSympho {
+            url: url.to_string(),
+            duration: Duration::from_secs(100),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut sympho_data = SymphoData {
+            volume: 0.5,
+            loop_mode: LoopMode::Queue,
+            text_channel: Some(ChannelId(7)),
+            ..Default::default()
+        };
+        sympho_data.queue = vec![
+            track("a"),
+            TrackSympho {
+                pending: Some(1),
+                ..track("pending")
+            },
+            track("b"),
+        ];
+
+        let snapshot = snapshot_of(&sympho_data, None, Some(ChannelId(42))).unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot = serde_json::from_str::<GuildSnapshot>(&json).unwrap();
+        assert_eq!(snapshot.voice_channel, Some(42));
+
+        let mut restored = SymphoData::default();
+        restore_snapshot(&mut restored, snapshot);
+        assert_eq!(
+            restored
+                .queue
+                .iter()
+                .map(|track| track.url.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+        assert_eq!(restored.queue_duration, Duration::from_secs(200));
+        assert_eq!(restored.volume, 0.5);
+        assert_eq!(restored.loop_mode, LoopMode::Queue);
+        assert_eq!(restored.text_channel, Some(ChannelId(7)));
+    }
+
+    #[test]
+    fn nothing_to_snapshot() {
+        assert!(snapshot_of(&SymphoData::default(), None, None).is_none());
+    }
+}
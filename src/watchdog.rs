@@ -0,0 +1,253 @@
+use crate::define::*;
+use crate::import::*;
+use crate::panel::*;
+use crate::player::*;
+
+// Nothing is playing and nothing is going to be played
+pub fn is_idle(sympho_data: &SymphoData) -> bool {
+    sympho_data.current.is_none()
+        && sympho_data.starting.is_none()
+        && sympho_data.queue.is_empty()
+        && sympho_data.resolving.is_empty()
+}
+
+// Leave by the watchdog, the text channel is told why
+async fn leave_by_watchdog(
+    data: &Arc<serenity::prelude::RwLock<TypeMap>>,
+    http: &Arc<Http>,
+    sympho_global_mutex: &SymphoMap,
+    key: u64,
+    reason: String,
+) {
+    let manager = if let Some(manager) = data.read().await.get::<songbird::SongbirdKey>().cloned() {
+        manager
+    } else {
+        return;
+    };
+    if manager.get(GuildId(key)).is_none() {
+        return;
+    }
+
+    let text_channel = sympho_global_mutex
+        .read()
+        .await
+        .get(&key)
+        .and_then(|sympho_data| sympho_data.text_channel);
+
+    if let Err(why) = leave_guild(http, &manager, sympho_global_mutex, GuildId(key)).await {
+        println!("Couldn't leave the guild {}: {:?}", key, why);
+        return;
+    }
+
+    if let Some(text_channel) = text_channel {
+        check_msg(text_channel.say(http, reason).await);
+    }
+}
+
+// Start the idle timer when the queue ran dry, the bot leaves if nothing is played until it fires
+pub async fn watch_idle(
+    data: &Arc<serenity::prelude::RwLock<TypeMap>>,
+    http: &Arc<Http>,
+    key: u64,
+) {
+    let sympho_global_mutex = {
+        let data = data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            sympho_global_mutex.clone()
+        } else {
+            return;
+        }
+    };
+
+    let (ticket, timeout) = {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = if let Some(sympho_data) = sympho_global.get_mut(&key) {
+            sympho_data
+        } else {
+            return;
+        };
+
+        match sympho_data.settings.idle_timeout {
+            Some(timeout) if is_idle(sympho_data) => {
+                // a new idle period, the timer of the last one must not fire early
+                let ticket = next_ticket();
+                sympho_data.idle_ticket = Some(ticket);
+                (ticket, timeout)
+            }
+            _ => return,
+        }
+    };
+
+    let data = data.clone();
+    let http = http.clone();
+    tokio::spawn(async move {
+        sleep(timeout).await;
+
+        let still_idle = sympho_global_mutex
+            .read()
+            .await
+            .get(&key)
+            .map_or(false, |sympho_data| {
+                sympho_data.idle_ticket == Some(ticket) && is_idle(sympho_data)
+            });
+
+        if still_idle {
+            leave_by_watchdog(
+                &data,
+                &http,
+                &sympho_global_mutex,
+                key,
+                format!(
+                    "Left the voice channel, nothing was played for {}.",
+                    dur_to_hhmmss(timeout)
+                ),
+            )
+            .await;
+        }
+    });
+}
+
+// Called on every voice state update of the guild.
+// the song is paused when the last listener leaves and resumed when someone comes back,
+// the bot leaves if no one came back until `alone_timeout`
+pub async fn check_listeners(ctx: &Context, guild_id: GuildId) {
+    let manager = if let Some(m) = songbird::get(ctx).await {
+        m
+    } else {
+        return;
+    };
+    let bot_channel = if let Some(handler_lock) = manager.get(guild_id) {
+        handler_lock
+            .lock()
+            .await
+            .current_channel()
+            .map(|bot_channel| ChannelId(bot_channel.0))
+    } else {
+        None
+    };
+    let bot_channel = if let Some(bot_channel) = bot_channel {
+        bot_channel
+    } else {
+        return;
+    };
+
+    let alone = if let Some(guild) = ctx.cache.guild(guild_id) {
+        listeners_in(&guild, bot_channel).is_empty()
+    } else {
+        return;
+    };
+
+    let sympho_global_mutex = {
+        let data = ctx.data.read().await;
+        if let Some(sympho_global_mutex) = data.get::<SymphoGlobal>() {
+            sympho_global_mutex.clone()
+        } else {
+            return;
+        }
+    };
+
+    let current = {
+        let mut sympho_global = sympho_global_mutex.write().await;
+        let sympho_data = guild_entry(&mut sympho_global, guild_id.0);
+
+        if !alone {
+            sympho_data.alone_ticket = None;
+            if sympho_data.paused_alone {
+                sympho_data.paused_alone = false;
+                if let Some((current, _)) = &sympho_data.current {
+                    let _ = current.play();
+                }
+                drop(sympho_global);
+                refresh_panel(&ctx.http, &sympho_global_mutex, guild_id.0).await;
+            }
+            return;
+        }
+
+        // the timer keeps running while the others join and leave other channels
+        if sympho_data.alone_ticket.is_none() {
+            if let Some(timeout) = sympho_data.settings.alone_timeout {
+                let ticket = next_ticket();
+                sympho_data.alone_ticket = Some(ticket);
+                spawn_alone_timer(
+                    ctx.clone(),
+                    sympho_global_mutex.clone(),
+                    guild_id.0,
+                    ticket,
+                    timeout,
+                );
+            }
+        }
+
+        sympho_data
+            .current
+            .as_ref()
+            .map(|(current, _)| current.clone())
+    };
+
+    // a song paused by someone stays paused when they come back
+    if let Some(current) = current {
+        let playing = current
+            .get_info()
+            .await
+            .map_or(false, |info| info.playing == PlayMode::Play);
+        if playing && current.pause().is_ok() {
+            if let Some(sympho_data) = sympho_global_mutex.write().await.get_mut(&guild_id.0) {
+                sympho_data.paused_alone = true;
+            }
+            refresh_panel(&ctx.http, &sympho_global_mutex, guild_id.0).await;
+        }
+    }
+}
+
+fn spawn_alone_timer(
+    ctx: Context,
+    sympho_global_mutex: SymphoMap,
+    key: u64,
+    ticket: u64,
+    timeout: Duration,
+) {
+    tokio::spawn(async move {
+        sleep(timeout).await;
+
+        // someone came back if the ticket was cleared
+        let still_alone = sympho_global_mutex
+            .read()
+            .await
+            .get(&key)
+            .map_or(false, |sympho_data| {
+                sympho_data.alone_ticket == Some(ticket)
+            });
+
+        if still_alone {
+            leave_by_watchdog(
+                &ctx.data,
+                &ctx.http,
+                &sympho_global_mutex,
+                key,
+                format!(
+                    "Left the voice channel, no one was listening for {}.",
+                    dur_to_hhmmss(timeout)
+                ),
+            )
+            .await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_guilds() {
+        let mut sympho_data = SymphoData::default();
+        assert!(is_idle(&sympho_data));
+
+        sympho_data.queue.push(TrackSympho::default());
+        assert!(!is_idle(&sympho_data));
+
+        sympho_data.queue.clear();
+        sympho_data.starting = Some(1);
+        assert!(!is_idle(&sympho_data));
+    }
+}
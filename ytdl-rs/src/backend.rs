@@ -0,0 +1,326 @@
+//! Extraction backends that `YoutubeDl::run` dispatches through.
+//!
+//! Every backend turns a configured `YoutubeDl` into the JSON document that
+//! `youtube-dl -J` would print, so `YoutubeDl::run` can parse the output the same way
+//! regardless of where it came from.
+
+use crate::{Error, YoutubeDl};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+
+#[cfg(feature = "subprocess")]
+use crate::ErrorInfo;
+#[cfg(feature = "subprocess")]
+use std::io::Read;
+#[cfg(feature = "subprocess")]
+use std::process::{Command, Stdio};
+#[cfg(feature = "subprocess")]
+use wait_timeout::ChildExt;
+
+#[cfg(feature = "pyo3")]
+use std::sync::{mpsc, Mutex};
+#[cfg(feature = "pyo3")]
+use youtube_dl_pyo3::{describe_error, interrupt_thread, YoutubeDl as YtDl};
+
+/// Future returned by `Backend::extract_info_async`.
+#[cfg(feature = "async")]
+pub type BackendFuture<'a> = Pin<Box<dyn Future<Output = Result<Value, Error>> + Send + 'a>>;
+
+#[cfg(not(any(feature = "pyo3", feature = "subprocess")))]
+compile_error!("ytdl-rs needs at least one of the `pyo3` or `subprocess` features");
+
+/// Something that can extract the info JSON of the url configured on a `YoutubeDl`.
+pub trait Backend: fmt::Debug + Send + Sync {
+    /// Extract the info of `ytdl.url()` and return it as the JSON value `youtube-dl -J` prints.
+    fn extract_info(&self, ytdl: &YoutubeDl) -> Result<Value, Error>;
+
+    /// Async version of `extract_info`, dropping the future cancels the extraction.
+    ///
+    /// The default implementation runs `extract_info` on tokio's blocking thread pool,
+    /// so a dropped future only discards the result while the blocking call runs to its end.
+    #[cfg(feature = "async")]
+    fn extract_info_async<'a>(&'a self, ytdl: &'a YoutubeDl) -> BackendFuture<'a> {
+        let ytdl = ytdl.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || ytdl.backend.extract_info(&ytdl))
+                .await
+                .map_err(|why| Error::Io(std::io::Error::other(why)))?
+        })
+    }
+}
+
+/// Calls the `youtube_dl` python module in-process through pyo3.
+///
+/// Only the options that map to `YoutubeDL` params are honoured, `user_agent`, `referer`,
+/// `all_formats`, `extract_audio` and extra args are ignored.
+/// When `process_timeout` expires, `TimeoutError` is raised in the python thread of the extraction
+/// and `Error::ProcessTimeout` is returned. The exception lands at the next python instruction,
+/// so a blocking socket read delays it until `socket_timeout` at most.
+#[cfg(feature = "pyo3")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pyo3Backend;
+
+/// Python thread of an extraction with a timeout, shared with the thread running it.
+#[cfg(feature = "pyo3")]
+#[derive(Debug, Default)]
+struct Extraction {
+    thread_id: Option<u64>,
+    timed_out: bool,
+}
+
+#[cfg(feature = "pyo3")]
+impl Backend for Pyo3Backend {
+    fn extract_info(&self, ytdl: &YoutubeDl) -> Result<Value, Error> {
+        let mut options = YtDl::options().set_quiet(true);
+
+        if let Some(format) = &ytdl.format {
+            options = options.set_format(format.clone());
+        }
+        if ytdl.flat_playlist {
+            options = options.set_extract_flat(true);
+        }
+        if let Some(timeout) = ytdl.socket_timeout {
+            options = options.set_socket_timeout(timeout);
+        }
+        if let Some((username, password)) = &ytdl.auth {
+            options = options
+                .set_username(username.clone())
+                .set_password(password.clone());
+        }
+        if let Some(cookie_path) = &ytdl.cookies {
+            options = options.set_cookiefile(cookie_path.clone());
+        }
+
+        let url = ytdl.url.clone();
+        let res = if let Some(timeout) = ytdl.process_timeout {
+            let extraction = Arc::new(Mutex::new(Extraction::default()));
+            let (tx, rx) = mpsc::channel();
+
+            let thread_extraction = extraction.clone();
+            std::thread::spawn(move || {
+                let res = options
+                    .build()
+                    .extract_info_interruptible(&url, |thread_id| {
+                        let mut extraction = thread_extraction
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner());
+                        extraction.thread_id = Some(thread_id);
+                        // the timeout expired before the thread got the GIL
+                        !extraction.timed_out
+                    });
+                let _ = tx.send(res);
+            });
+
+            match rx.recv_timeout(timeout) {
+                Ok(res) => res,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let thread_id = {
+                        let mut extraction = extraction
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner());
+                        extraction.timed_out = true;
+                        extraction.thread_id
+                    };
+                    // not under the lock, the GIL may be taken by the thread waiting for it
+                    if let Some(thread_id) = thread_id {
+                        interrupt_thread(thread_id);
+                    }
+                    return Err(Error::ProcessTimeout);
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(Error::classify(None, "extraction thread panicked"))
+                }
+            }
+        } else {
+            options.build().extract_info(&url).map(Some)
+        };
+
+        let res = match res {
+            Ok(Some(r)) => r,
+            // skipped since the timeout expired first
+            Ok(None) => return Err(Error::ProcessTimeout),
+            Err(err) => {
+                let (exception, message) = describe_error(&err);
+                return Err(Error::classify(Some(exception), message));
+            }
+        };
+
+        Ok(serde_json::from_str(&res)?)
+    }
+}
+
+/// Spawns the `youtube-dl` (or `yt-dlp`) executable and parses its `-J` output.
+#[cfg(feature = "subprocess")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SubprocessBackend;
+
+#[cfg(feature = "subprocess")]
+impl Backend for SubprocessBackend {
+    fn extract_info(&self, ytdl: &YoutubeDl) -> Result<Value, Error> {
+        let mut child = Command::new(ytdl.path())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .args(ytdl.process_args())
+            .spawn()
+            .map_err(|err| spawn_error(ytdl, err))?;
+
+        // Read stdout and stderr on their own threads so that a large output does not fill up
+        // a pipe and hang forever, and so that the process timeout below still applies while reading.
+        let stdout_reader = drain(child.stdout.take());
+        let stderr_reader = drain(child.stderr.take());
+
+        let exit_code = if let Some(timeout) = ytdl.process_timeout {
+            match child.wait_timeout(timeout)? {
+                Some(status) => status,
+                None => {
+                    child.kill()?;
+                    let _ = child.wait();
+                    return Err(Error::ProcessTimeout);
+                }
+            }
+        } else {
+            child.wait()?
+        };
+
+        let stdout = stdout_reader.join().unwrap_or_else(|_| Ok(Vec::new()))?;
+        let stderr = stderr_reader.join().unwrap_or_else(|_| Ok(Vec::new()))?;
+
+        if exit_code.success() {
+            Ok(serde_json::from_reader(stdout.as_slice())?)
+        } else {
+            let stderr = String::from_utf8(stderr).unwrap_or_default();
+            let code = exit_code.code().unwrap_or(1);
+            Err(exit_error(code, stderr))
+        }
+    }
+
+    /// Runs the executable with `tokio::process`, the child is killed when the future is dropped.
+    #[cfg(feature = "async")]
+    fn extract_info_async<'a>(&'a self, ytdl: &'a YoutubeDl) -> BackendFuture<'a> {
+        Box::pin(async move {
+            let output = tokio::process::Command::new(ytdl.path())
+                .stdin(Stdio::null())
+                .args(ytdl.process_args())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|err| spawn_error(ytdl, err))?;
+
+            if output.status.success() {
+                Ok(serde_json::from_slice(&output.stdout)?)
+            } else {
+                let stderr = String::from_utf8(output.stderr).unwrap_or_default();
+                let code = output.status.code().unwrap_or(1);
+                Err(exit_error(code, stderr))
+            }
+        })
+    }
+}
+
+/// Read a pipe of the child to its end on a new thread.
+#[cfg(feature = "subprocess")]
+fn drain<R: Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut reader) = pipe {
+            reader.read_to_end(&mut output)?;
+        }
+        Ok(output)
+    })
+}
+
+/// A missing executable is reported as `Error::NotInstalled`.
+#[cfg(feature = "subprocess")]
+fn spawn_error(ytdl: &YoutubeDl, err: std::io::Error) -> Error {
+    if err.kind() == std::io::ErrorKind::NotFound {
+        Error::NotInstalled(ErrorInfo {
+            exception: None,
+            message: format!("{} not found", ytdl.path().display()),
+        })
+    } else {
+        Error::Io(err)
+    }
+}
+
+/// Classify the last `ERROR:` line of stderr, keeping `Error::ExitCode` when it is not recognized.
+#[cfg(feature = "subprocess")]
+fn exit_error(code: i32, stderr: String) -> Error {
+    let message = stderr
+        .lines()
+        .rev()
+        .find(|line| line.starts_with("ERROR:"))
+        .map(|line| line.to_string());
+
+    match message.map(|message| Error::classify(None, message)) {
+        Some(Error::Extractor(_)) | None => Error::ExitCode { code, stderr },
+        Some(err) => err,
+    }
+}
+
+/// The backends compiled into this build, used to pick one at runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    /// `Pyo3Backend`
+    #[cfg(feature = "pyo3")]
+    Pyo3,
+    /// `SubprocessBackend`
+    #[cfg(feature = "subprocess")]
+    Subprocess,
+}
+
+impl BackendKind {
+    /// Create the backend of this kind.
+    pub fn backend(self) -> Arc<dyn Backend> {
+        match self {
+            #[cfg(feature = "pyo3")]
+            Self::Pyo3 => Arc::new(Pyo3Backend),
+            #[cfg(feature = "subprocess")]
+            Self::Subprocess => Arc::new(SubprocessBackend),
+        }
+    }
+}
+
+impl Default for BackendKind {
+    /// pyo3 when it is compiled in, otherwise the subprocess backend.
+    fn default() -> Self {
+        #[cfg(feature = "pyo3")]
+        return Self::Pyo3;
+        #[cfg(not(feature = "pyo3"))]
+        return Self::Subprocess;
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    /// Parse a backend name, fails if the backend was not compiled in.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            #[cfg(feature = "pyo3")]
+            "pyo3" | "python" => Ok(Self::Pyo3),
+            #[cfg(feature = "subprocess")]
+            "subprocess" | "process" | "cli" => Ok(Self::Subprocess),
+            _ => Err(format!("unknown or disabled ytdl backend: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "pyo3")]
+            Self::Pyo3 => write!(f, "pyo3"),
+            #[cfg(feature = "subprocess")]
+            Self::Subprocess => write!(f, "subprocess"),
+        }
+    }
+}
@@ -1,13 +1,15 @@
+pub mod backend;
 pub mod model;
+pub use crate::backend::*;
 pub use crate::model::*;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::error::Error as StdError;
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
-use youtube_dl_pyo3::YoutubeDl as YtDl;
-use youtube_dl_pyo3::YoutubeDlOptions as YtDlOptions;
 
 /// Data returned by `YoutubeDl::run`. Output can either be a single video or a playlist of videos.
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -20,14 +22,14 @@ pub enum YoutubeDlOutput {
 
 impl YoutubeDlOutput {
     #[cfg(test)]
-    fn to_single_video(self) -> SingleVideo {
+    fn into_single_video(self) -> SingleVideo {
         match self {
             YoutubeDlOutput::SingleVideo(video) => *video,
             _ => panic!("this is a playlist, not a single video"),
         }
     }
     #[cfg(test)]
-    fn to_playlist(self) -> Playlist {
+    fn into_playlist(self) -> Playlist {
         match self {
             YoutubeDlOutput::Playlist(playlist) => *playlist,
             _ => panic!("this is a playlist, not a single video"),
@@ -35,6 +37,25 @@ impl YoutubeDlOutput {
     }
 }
 
+/// Exception type and message of a failed extraction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorInfo {
+    /// Name of the python exception (e.g. `UnsupportedError`), `None` when it is unknown
+    /// (e.g. parsed from the stderr of the `youtube-dl` executable).
+    pub exception: Option<String>,
+    /// The error message.
+    pub message: String,
+}
+
+impl fmt::Display for ErrorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.exception {
+            Some(exception) => write!(f, "{}: {}", exception, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
 /// Errors that can occur during executing `youtube-dl` or during parsing the output.
 #[derive(Debug)]
 pub enum Error {
@@ -55,8 +76,87 @@ pub enum Error {
     /// Process-level timeout expired.
     ProcessTimeout,
 
-    /// Pyo3 Error.
-    Pyo3Error,
+    /// No youtube-dl extractor supports the url.
+    UnsupportedUrl(ErrorInfo),
+
+    /// The video is private, removed or not available in this country.
+    Unavailable(ErrorInfo),
+
+    /// The video needs a signed in account to confirm the age.
+    AgeRestricted(ErrorInfo),
+
+    /// The site could not be reached.
+    Network(ErrorInfo),
+
+    /// The `youtube_dl` python module or the `youtube-dl` executable is not installed.
+    NotInstalled(ErrorInfo),
+
+    /// Any other exception raised by youtube-dl.
+    Extractor(ErrorInfo),
+}
+
+impl Error {
+    /// Build the variant matching a youtube-dl failure from its exception name and message.
+    pub fn classify(exception: Option<String>, message: impl Into<String>) -> Self {
+        let info = ErrorInfo {
+            exception,
+            message: message.into(),
+        };
+        let exception = info.exception.as_deref().unwrap_or("");
+        let message = info.message.to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|word| message.contains(word));
+
+        if matches!(exception, "ModuleNotFoundError" | "ImportError") || has(&["no module named"]) {
+            Self::NotInstalled(info)
+        } else if exception == "UnsupportedError" || has(&["unsupported url"]) {
+            Self::UnsupportedUrl(info)
+        } else if has(&[
+            "confirm your age",
+            "age-restricted",
+            "age restricted",
+            "inappropriate for some users",
+        ]) {
+            Self::AgeRestricted(info)
+        } else if exception == "GeoRestrictedError"
+            || has(&[
+                "not available in your country",
+                "video unavailable",
+                "this video is unavailable",
+                "private video",
+                "has been removed",
+                // not `is not available`, that is also `requested format is not available`
+                "this video is not available",
+            ])
+        {
+            Self::Unavailable(info)
+        } else if matches!(
+            exception,
+            "URLError" | "HTTPError" | "timeout" | "SSLError" | "ConnectionError"
+        ) || has(&[
+            "unable to download webpage",
+            "urlopen error",
+            "timed out",
+            "http error",
+            "connection reset",
+        ]) {
+            Self::Network(info)
+        } else {
+            Self::Extractor(info)
+        }
+    }
+
+    /// The exception type and message, for the variants raised by youtube-dl itself.
+    pub fn info(&self) -> Option<&ErrorInfo> {
+        match self {
+            Self::UnsupportedUrl(info)
+            | Self::Unavailable(info)
+            | Self::AgeRestricted(info)
+            | Self::Network(info)
+            | Self::NotInstalled(info)
+            | Self::Extractor(info) => Some(info),
+            _ => None,
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -80,7 +180,12 @@ impl fmt::Display for Error {
                 write!(f, "non-zero exit code: {}, stderr: {}", code, stderr)
             }
             Self::ProcessTimeout => write!(f, "process timed out"),
-            Self::Pyo3Error => write!(f, "pyo3 error raised"),
+            Self::UnsupportedUrl(info) => write!(f, "unsupported url: {}", info),
+            Self::Unavailable(info) => write!(f, "video unavailable: {}", info),
+            Self::AgeRestricted(info) => write!(f, "age restricted: {}", info),
+            Self::Network(info) => write!(f, "network error: {}", info),
+            Self::NotInstalled(info) => write!(f, "youtube-dl not installed: {}", info),
+            Self::Extractor(info) => write!(f, "extractor error: {}", info),
         }
     }
 }
@@ -90,9 +195,7 @@ impl StdError for Error {
         match self {
             Self::Io(err) => Some(err),
             Self::Json(err) => Some(err),
-            Self::ExitCode { .. } => None,
-            Self::ProcessTimeout => None,
-            Self::Pyo3Error => None,
+            _ => None,
         }
     }
 }
@@ -194,40 +297,40 @@ impl fmt::Display for SearchOptions {
 /// A builder to create a `youtube-dl` command to execute.
 #[derive(Clone, Debug)]
 pub struct YoutubeDl {
-    inner_options: YtDlOptions,
+    backend: Arc<dyn Backend>,
     youtube_dl_path: Option<PathBuf>,
-    _format: Option<String>,
-    _flat_playlist: bool,
-    _socket_timeout: Option<String>,
-    _all_formats: bool,
-    _auth: Option<(String, String)>,
-    _cookies: Option<String>,
-    _user_agent: Option<String>,
-    _referer: Option<String>,
+    format: Option<String>,
+    flat_playlist: bool,
+    socket_timeout: Option<i32>,
+    all_formats: bool,
+    auth: Option<(String, String)>,
+    cookies: Option<String>,
+    user_agent: Option<String>,
+    referer: Option<String>,
     url: String,
-    _process_timeout: Option<Duration>,
-    _extract_audio: bool,
-    _extra_args: Vec<String>,
+    process_timeout: Option<Duration>,
+    extract_audio: bool,
+    extra_args: Vec<String>,
 }
 
 impl YoutubeDl {
     /// Create a new builder.
     pub fn new(url: impl Into<String>) -> Self {
         Self {
-            inner_options: YtDl::options(),
+            backend: BackendKind::default().backend(),
             url: url.into(),
             youtube_dl_path: None,
-            _format: None,
-            _flat_playlist: false,
-            _socket_timeout: None,
-            _all_formats: false,
-            _auth: None,
-            _cookies: None,
-            _user_agent: None,
-            _referer: None,
-            _process_timeout: None,
-            _extract_audio: false,
-            _extra_args: Vec::new(),
+            format: None,
+            flat_playlist: false,
+            socket_timeout: None,
+            all_formats: false,
+            auth: None,
+            cookies: None,
+            user_agent: None,
+            referer: None,
+            process_timeout: None,
+            extract_audio: false,
+            extra_args: Vec::new(),
         }
     }
 
@@ -236,6 +339,18 @@ impl YoutubeDl {
         Self::new(options.to_string())
     }
 
+    /// Set the backend that `run` dispatches through.
+    pub fn backend<B: Backend + 'static>(&mut self, backend: B) -> &mut Self {
+        self.backend = Arc::new(backend);
+        self
+    }
+
+    /// Set the backend that `run` dispatches through from one of the compiled in kinds.
+    pub fn backend_kind(&mut self, kind: BackendKind) -> &mut Self {
+        self.backend = kind.backend();
+        self
+    }
+
     /// Set the path to the `youtube-dl` executable.
     pub fn youtube_dl_path<P: AsRef<Path>>(&mut self, youtube_dl_path: P) -> &mut Self {
         self.youtube_dl_path = Some(youtube_dl_path.as_ref().to_owned());
@@ -244,26 +359,22 @@ impl YoutubeDl {
 
     /// Set the `-f` command line option.
     pub fn format<S: Into<String>>(&mut self, format: S) -> &mut Self {
-        self.inner_options = self.inner_options.clone().set_format(format.into());
+        self.format = Some(format.into());
         self
     }
 
     /// Set the `--flat-playlist` command line flag.
     pub fn flat_playlist(&mut self, flat_playlist: bool) -> &mut Self {
-        self.inner_options = self.inner_options.clone().set_extract_flat(flat_playlist);
+        self.flat_playlist = flat_playlist;
         self
     }
 
     /// Set the `--socket-timeout` command line flag.
     pub fn socket_timeout<I: Into<i32>>(&mut self, socket_timeout: I) -> &mut Self {
-        self.inner_options = self
-            .inner_options
-            .clone()
-            .set_socket_timeout(socket_timeout.into());
+        self.socket_timeout = Some(socket_timeout.into());
         self
     }
 
-    /*
     /// Set the `--user-agent` command line flag.
     pub fn user_agent<S: Into<String>>(&mut self, user_agent: S) -> &mut Self {
         self.user_agent = Some(user_agent.into());
@@ -281,28 +392,19 @@ impl YoutubeDl {
         self.all_formats = all_formats;
         self
     }
-    */
 
     /// Set the `-u` and `-p` command line flags.
     pub fn auth<S: Into<String>>(&mut self, username: S, password: S) -> &mut Self {
-        self.inner_options = self
-            .inner_options
-            .clone()
-            .set_username(username.into())
-            .set_password(password.into());
+        self.auth = Some((username.into(), password.into()));
         self
     }
 
     /// Specify a file with cookies in Netscape cookie format.
     pub fn cookies<S: Into<String>>(&mut self, cookie_path: S) -> &mut Self {
-        self.inner_options = self
-            .inner_options
-            .clone()
-            .set_cookiefile(cookie_path.into());
+        self.cookies = Some(cookie_path.into());
         self
     }
 
-    /*
     /// Set a process-level timeout for youtube-dl. (this controls the maximum overall duration
     /// the process may take, when it times out, `Error::ProcessTimeout` is returned)
     pub fn process_timeout(&mut self, timeout: Duration) -> &mut Self {
@@ -325,99 +427,122 @@ impl YoutubeDl {
         self
     }
 
-    fn path(&self) -> &Path {
+    /// The url (or search query) to extract.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The path of the `youtube-dl` executable used by the subprocess backend.
+    pub fn path(&self) -> &Path {
         match &self.youtube_dl_path {
             Some(path) => path,
             None => Path::new("youtube-dl"),
         }
     }
 
-    fn process_args(&self) -> Vec<&str> {
+    /// The command line arguments equivalent to this builder, ending with `-J <url>`.
+    pub fn process_args(&self) -> Vec<String> {
         let mut args = vec![];
         if let Some(format) = &self.format {
-            args.push("-f");
-            args.push(format);
+            args.push("-f".to_string());
+            args.push(format.clone());
         }
 
         if self.flat_playlist {
-            args.push("--flat-playlist");
+            args.push("--flat-playlist".to_string());
         }
 
         if let Some(timeout) = &self.socket_timeout {
-            args.push("--socket-timeout");
-            args.push(timeout);
+            args.push("--socket-timeout".to_string());
+            args.push(timeout.to_string());
         }
 
         if self.all_formats {
-            args.push("--all-formats");
+            args.push("--all-formats".to_string());
         }
 
         if let Some((user, password)) = &self.auth {
-            args.push("-u");
-            args.push(user);
-            args.push("-p");
-            args.push(password);
+            args.push("-u".to_string());
+            args.push(user.clone());
+            args.push("-p".to_string());
+            args.push(password.clone());
         }
 
         if let Some(cookie_path) = &self.cookies {
-            args.push("--cookies");
-            args.push(cookie_path);
+            args.push("--cookies".to_string());
+            args.push(cookie_path.clone());
         }
 
         if let Some(user_agent) = &self.user_agent {
-            args.push("--user-agent");
-            args.push(user_agent);
+            args.push("--user-agent".to_string());
+            args.push(user_agent.clone());
         }
 
         if let Some(referer) = &self.referer {
-            args.push("--referer");
-            args.push(referer);
+            args.push("--referer".to_string());
+            args.push(referer.clone());
         }
 
         if self.extract_audio {
-            args.push("--extract-audio");
+            args.push("--extract-audio".to_string());
         }
 
         for extra_arg in &self.extra_args {
-            args.push(extra_arg);
+            args.push(extra_arg.clone());
         }
 
-        args.push("-J");
-        args.push(&self.url);
+        args.push("-J".to_string());
+        args.push(self.url.clone());
         log::debug!("youtube-dl arguments: {:?}", args);
 
         args
     }
-    */
 
     /// Run youtube-dl with the arguments specified through the builder.
     pub fn run(&self) -> Result<YoutubeDlOutput, Error> {
-        use serde_json::{json, Value};
+        let json_res: Value = self.backend.extract_info(self)?;
 
-        let ytdl = self.inner_options.clone().set_quiet(true).build();
+        parse_output(json_res)
+    }
 
-        let res = if let Ok(r) = ytdl.extract_info(&self.url) {
-            r
+    /// Async version of `run`, must be called inside a tokio runtime.
+    ///
+    /// When `process_timeout` expires, `Error::ProcessTimeout` is returned.
+    /// Dropping the returned future cancels the extraction (the subprocess backend kills the child).
+    #[cfg(feature = "async")]
+    pub async fn run_async(&self) -> Result<YoutubeDlOutput, Error> {
+        let extraction = self.backend.extract_info_async(self);
+
+        let json_res: Value = if let Some(timeout) = self.process_timeout {
+            match tokio::time::timeout(timeout, extraction).await {
+                Ok(res) => res?,
+                Err(_) => return Err(Error::ProcessTimeout),
+            }
         } else {
-            return Err(Error::Pyo3Error);
+            extraction.await?
         };
 
-        let json_res: Value = serde_json::from_str(&res)?;
+        parse_output(json_res)
+    }
+}
 
-        let is_playlist = json_res["_type"] == json!("playlist");
-        if is_playlist {
-            let playlist: Playlist = serde_json::from_value(json_res)?;
-            Ok(YoutubeDlOutput::Playlist(Box::new(playlist)))
-        } else {
-            let video: SingleVideo = serde_json::from_value(json_res)?;
-            Ok(YoutubeDlOutput::SingleVideo(Box::new(video)))
-        }
+fn parse_output(json_res: Value) -> Result<YoutubeDlOutput, Error> {
+    use serde_json::json;
+
+    let is_playlist = json_res["_type"] == json!("playlist");
+    if is_playlist {
+        let playlist: Playlist = serde_json::from_value(json_res)?;
+        Ok(YoutubeDlOutput::Playlist(Box::new(playlist)))
+    } else {
+        let video: SingleVideo = serde_json::from_value(json_res)?;
+        Ok(YoutubeDlOutput::SingleVideo(Box::new(video)))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{SearchOptions, YoutubeDl};
+    use crate::{BackendKind, Error, SearchOptions, YoutubeDl};
+    use std::time::Duration;
 
     #[test]
     fn test_youtube_url() {
@@ -425,7 +550,7 @@ mod tests {
             .socket_timeout(15)
             .run()
             .unwrap()
-            .to_single_video();
+            .into_single_video();
         assert_eq!(output.id, "7XGyWcuYVrg");
     }
 
@@ -435,7 +560,7 @@ mod tests {
             .socket_timeout(15)
             .run()
             .unwrap()
-            .to_single_video();
+            .into_single_video();
         assert_eq!(output.id, "7XGyWcuYVrg");
     }
 
@@ -453,7 +578,7 @@ mod tests {
             .socket_timeout(15)
             .run()
             .unwrap()
-            .to_playlist();
+            .into_playlist();
         assert_eq!(output.entries.unwrap().first().unwrap().id, "dQw4w9WgXcQ");
     }
 
@@ -462,7 +587,7 @@ mod tests {
         let output = YoutubeDl::new("https://youtube.com/watch?v=sAD1nayZ9dk")
             .run()
             .unwrap()
-            .to_single_video();
+            .into_single_video();
 
         assert_eq!(output.season_number, Some(2));
     }
@@ -472,7 +597,7 @@ mod tests {
         let output = YoutubeDl::new("https://www.youtube.com/watch?v=WhWc3b3KhnY")
             .run()
             .unwrap()
-            .to_single_video();
+            .into_single_video();
 
         let mut none_counter = 0;
         for format in output.formats.unwrap() {
@@ -484,4 +609,174 @@ mod tests {
         }
         assert!(none_counter > 0);
     }
+
+    #[test]
+    fn process_args_follow_builder() {
+        let args = YoutubeDl::new("https://www.youtube.com/watch?v=7XGyWcuYVrg")
+            .flat_playlist(true)
+            .socket_timeout(15)
+            .user_agent("sympho")
+            .referer("https://example.com")
+            .extra_arg("--no-cache-dir")
+            .process_timeout(Duration::from_secs(30))
+            .process_args();
+
+        assert_eq!(
+            args,
+            vec![
+                "--flat-playlist",
+                "--socket-timeout",
+                "15",
+                "--user-agent",
+                "sympho",
+                "--referer",
+                "https://example.com",
+                "--no-cache-dir",
+                "-J",
+                "https://www.youtube.com/watch?v=7XGyWcuYVrg",
+            ]
+        );
+    }
+
+    #[test]
+    fn backend_kind_round_trip() {
+        let kind = BackendKind::default();
+        assert_eq!(kind.to_string().parse::<BackendKind>(), Ok(kind));
+        assert!("nope".parse::<BackendKind>().is_err());
+    }
+
+    #[cfg(feature = "subprocess")]
+    #[test]
+    fn test_subprocess_backend() {
+        let output = YoutubeDl::new("https://www.youtube.com/watch?v=7XGyWcuYVrg")
+            .backend_kind(BackendKind::Subprocess)
+            .socket_timeout(15)
+            .process_timeout(Duration::from_secs(60))
+            .run()
+            .unwrap()
+            .into_single_video();
+        assert_eq!(output.id, "7XGyWcuYVrg");
+    }
+
+    #[cfg(all(unix, feature = "subprocess"))]
+    #[test]
+    fn subprocess_process_timeout() {
+        let err = YoutubeDl::new("https://www.youtube.com/watch?v=7XGyWcuYVrg")
+            .backend_kind(BackendKind::Subprocess)
+            .youtube_dl_path("sh")
+            .extra_arg("-c")
+            .extra_arg("sleep 5")
+            .process_timeout(Duration::from_millis(200))
+            .run()
+            .unwrap_err();
+        assert!(matches!(err, Error::ProcessTimeout));
+    }
+
+    #[cfg(all(unix, feature = "subprocess"))]
+    #[test]
+    fn subprocess_large_stderr() {
+        // more than a pipe buffer, the child hangs until the timeout if stderr is not drained
+        let err = YoutubeDl::new("https://www.youtube.com/watch?v=7XGyWcuYVrg")
+            .backend_kind(BackendKind::Subprocess)
+            .youtube_dl_path("sh")
+            .extra_arg("-c")
+            .extra_arg("head -c 200000 /dev/zero | tr '\\0' x >&2; exit 3")
+            .process_timeout(Duration::from_secs(10))
+            .run()
+            .unwrap_err();
+        match err {
+            Error::ExitCode { code, stderr } => {
+                assert_eq!(code, 3);
+                assert_eq!(stderr.len(), 200000);
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[cfg(all(unix, feature = "subprocess", feature = "async"))]
+    #[tokio::test]
+    async fn subprocess_process_timeout_async() {
+        let err = YoutubeDl::new("https://www.youtube.com/watch?v=7XGyWcuYVrg")
+            .backend_kind(BackendKind::Subprocess)
+            .youtube_dl_path("sh")
+            .extra_arg("-c")
+            .extra_arg("sleep 5")
+            .process_timeout(Duration::from_millis(200))
+            .run_async()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ProcessTimeout));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_youtube_url_async() {
+        let output = YoutubeDl::new("https://www.youtube.com/watch?v=7XGyWcuYVrg")
+            .socket_timeout(15)
+            .run_async()
+            .await
+            .unwrap()
+            .into_single_video();
+        assert_eq!(output.id, "7XGyWcuYVrg");
+    }
+
+    #[test]
+    fn classify_errors() {
+        let unsupported = Error::classify(
+            Some("UnsupportedError".to_string()),
+            "ERROR: Unsupported URL: https://www.rust-lang.org",
+        );
+        assert!(matches!(unsupported, Error::UnsupportedUrl(_)));
+
+        let geo = Error::classify(
+            Some("GeoRestrictedError".to_string()),
+            "ERROR: The uploader has not made this video available in your country.",
+        );
+        assert!(matches!(geo, Error::Unavailable(_)));
+
+        let private = Error::classify(None, "ERROR: Private video");
+        assert!(matches!(private, Error::Unavailable(_)));
+
+        let removed = Error::classify(None, "ERROR: This video is not available.");
+        assert!(matches!(removed, Error::Unavailable(_)));
+
+        let format = Error::classify(
+            Some("ExtractorError".to_string()),
+            "ERROR: requested format is not available",
+        );
+        assert!(matches!(format, Error::Extractor(_)));
+
+        let age = Error::classify(
+            Some("ExtractorError".to_string()),
+            "ERROR: Sign in to confirm your age",
+        );
+        assert!(matches!(age, Error::AgeRestricted(_)));
+
+        let network = Error::classify(
+            Some("ExtractorError".to_string()),
+            "ERROR: Unable to download webpage: <urlopen error [Errno -2] Name or service not known>",
+        );
+        assert!(matches!(network, Error::Network(_)));
+
+        let module = Error::classify(
+            Some("ModuleNotFoundError".to_string()),
+            "No module named 'youtube_dl'",
+        );
+        assert!(matches!(module, Error::NotInstalled(_)));
+
+        let other = Error::classify(Some("KeyError".to_string()), "'id'");
+        assert_eq!(
+            other.info().map(|info| info.to_string()),
+            Some("KeyError: 'id'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unknown_url_is_unsupported() {
+        let err = YoutubeDl::new("https://www.rust-lang.org")
+            .socket_timeout(15)
+            .run()
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedUrl(_)));
+    }
 }
@@ -1,5 +1,6 @@
 use pyo3::prelude::Python;
-use pyo3::PyResult;
+use pyo3::exceptions::PyValueError;
+use pyo3::{PyErr, PyResult};
 use pyo3::types::{PyString, PyDict, PyList};
 use serde_json;
 
@@ -366,13 +367,13 @@ impl YoutubeDl {
         YoutubeDl::options().build()
     }
 
-    fn prepare_options(self, py: Python) -> &PyDict {
+    fn prepare_options(self, py: Python) -> PyResult<&PyDict> {
         let options = PyDict::new(py);
 
         macro_rules! declare_pydict_setter {
             ( $attribute:ident ) => {
                 if self.options.$attribute.is_some() {
-                    options.set_item(stringify!($attribute), self.options.$attribute).expect("Failed to set ytdl option");
+                    options.set_item(stringify!($attribute), self.options.$attribute)?;
                 }
             }
         }
@@ -461,7 +462,7 @@ impl YoutubeDl {
         declare_pydict_setter!(postproessor_args);
         declare_pydict_setter!(youtube_include_dash_manifest);
 
-        return options;
+        Ok(options)
     }
 
     ///
@@ -474,9 +475,9 @@ impl YoutubeDl {
         let locals = PyDict::new(py);
 
         // Set python context
-        locals.set_item("urls", urls).expect("Failed to pass url to python");
-        locals.set_item("options", self.prepare_options(py)).expect("Failed to pass ytdl options");
-        locals.set_item("youtube_dl", py.import("youtube_dl").expect("Failed to import youtube_dl")).expect("Failed to pass ytdl to python");
+        locals.set_item("urls", urls)?;
+        locals.set_item("options", self.prepare_options(py)?)?;
+        locals.set_item("youtube_dl", py.import("youtube_dl")?)?;
 
         let res = py.eval(include_str!("py/download.py"), None, Some(&locals));
 
@@ -495,22 +496,47 @@ impl YoutubeDl {
         let locals = PyDict::new(py);
 
         // Set python context
-        locals.set_item("url", url).expect("Failed to pass url to python");
-        locals.set_item("options", self.prepare_options(py)).expect("Failed to pass ytdl options");
-        locals.set_item("youtube_dl", py.import("youtube_dl").expect("Failed to import youtube_dl")).expect("Failed to pass ytdl to python");
-        locals.set_item("json", py.import("json").expect("Failed to import json")).expect("Failed to pass json module to python");
+        locals.set_item("url", url)?;
+        locals.set_item("options", self.prepare_options(py)?)?;
+        locals.set_item("youtube_dl", py.import("youtube_dl")?)?;
+        locals.set_item("json", py.import("json")?)?;
 
         let ret = py.eval(include_str!("py/extract_info.py"), None, Some(&locals))?;
 
         Ok(ret.downcast::<PyString>()?.extract::<String>()?)
     }
 
+    ///
+    /// Like extract_info, but `on_start` is called with the python thread id before the extraction starts,
+    /// and the extraction is skipped (Ok(None)) if it returns false.
+    /// The GIL state of the thread is kept from `on_start` to the end of the extraction,
+    /// so `interrupt_thread` with that id stops this extraction.
+    ///
+    pub fn extract_info_interruptible(
+        self,
+        url: &str,
+        on_start: impl FnOnce(u64) -> bool,
+    ) -> pyo3::PyResult<Option<String>> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let thread_id = py
+            .import("threading")?
+            .call_method0("get_ident")?
+            .extract::<u64>()?;
+        if !on_start(thread_id) {
+            return Ok(None);
+        }
+
+        self.extract_info(url).map(Some)
+    }
+
     ///
     /// Extract information about the video at the provided url
     ///
     pub fn extract_video(self, url: &str) -> PyResult<Video> {
         let json_string = self.extract_info(url)?;
-        Ok(serde_json::from_str::<Video>(&json_string).unwrap())
+        serde_json::from_str::<Video>(&json_string).map_err(|e| PyValueError::new_err(e.to_string()))
     }
 
     ///
@@ -518,7 +544,7 @@ impl YoutubeDl {
     ///
     pub fn extract_playlist(self, url: &str) -> PyResult<Playlist> {
         let json_string = self.extract_info(url)?;
-        Ok(serde_json::from_str::<Playlist>(&json_string).unwrap())
+        serde_json::from_str::<Playlist>(&json_string).map_err(|e| PyValueError::new_err(e.to_string()))
     }
 
     ///
@@ -529,7 +555,7 @@ impl YoutubeDl {
         let py = gil.python();
 
         let locals = PyDict::new(py);
-        locals.set_item("youtube_dl", py.import("youtube_dl").expect("Failed to import youtube_dl")).expect("Failed to pass ytdl to python");
+        locals.set_item("youtube_dl", py.import("youtube_dl")?)?;
 
         let py_extractors_any = py.eval(include_str!("py/list_extractors.py"), None, Some(&locals))?;
 
@@ -541,12 +567,47 @@ impl YoutubeDl {
     }
 }
 
+///
+/// Raise `TimeoutError` in the python thread `thread_id`, returns false if there is no such thread.
+/// The exception is raised at the next python instruction the thread runs,
+/// so a blocking socket read is stopped only once it returns (see the `socket_timeout` option).
+///
+pub fn interrupt_thread(thread_id: u64) -> bool {
+    Python::with_gil(|_py| unsafe {
+        pyo3::ffi::PyThreadState_SetAsyncExc(thread_id as _, pyo3::ffi::PyExc_TimeoutError) == 1
+    })
+}
+
+///
+/// Get the exception type name and the message of a python error.
+/// For youtube-dl's `DownloadError`, the name of the wrapped exception (e.g. `UnsupportedError`) is used.
+///
+pub fn describe_error(err: &PyErr) -> (String, String) {
+    Python::with_gil(|py| {
+        let value = err.pvalue(py);
+        let message = value.to_string();
+
+        let wrapped = value
+            .getattr("exc_info")
+            .and_then(|exc_info| exc_info.get_item(0))
+            .and_then(|exc_type| exc_type.getattr("__name__"))
+            .and_then(|name| name.extract::<String>());
+
+        let exception = match wrapped {
+            Ok(name) => name,
+            Err(_) => err.ptype(py).name().map(|name| name.to_string()).unwrap_or_default()
+        };
+
+        (exception, message)
+    })
+}
+
 #[test]
 fn test_default_options() {
     let ytdl = YoutubeDl::new();
 
     let gil = Python::acquire_gil();
-    let pydict = ytdl.prepare_options(gil.python());
+    let pydict = ytdl.prepare_options(gil.python()).unwrap();
     assert!(pydict.get_item("username").is_none());
     assert!(pydict.get_item("password").is_none());
     assert!(pydict.get_item("videopassword").is_none());
@@ -639,7 +700,7 @@ fn test_set_options() {
         .build();
 
     let gil = Python::acquire_gil();
-    let pydict = ytdl.prepare_options(gil.python());
+    let pydict = ytdl.prepare_options(gil.python()).unwrap();
 
     assert_eq!(pydict.get_item("username").unwrap().downcast::<PyString>().unwrap().extract::<String>().unwrap(), "Hello".to_owned());
     assert_eq!(pydict.get_item("password").unwrap().downcast::<PyString>().unwrap().extract::<String>().unwrap(), "World".to_owned());